@@ -0,0 +1,94 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use failure::{Error, Fail, ResultExt};
+use serde_derive::{Deserialize, Serialize};
+
+/// The answers file checked in next to the puzzle inputs.
+pub const DEFAULT_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/answers.toml");
+
+/// The known-correct answers for each day, as stored in `answers.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AnswerFile {
+    #[serde(default, rename = "day")]
+    pub days: Vec<Recorded>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recorded {
+    pub number: u32,
+    pub part1: String,
+    pub part2: String,
+}
+
+impl AnswerFile {
+    /// Load the answers file, treating a missing file as empty.
+    pub fn load(path: &Path) -> Result<AnswerFile, Error> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(AnswerFile::default());
+            }
+            Err(e) => {
+                let context = format!("Failed to read answers file {}", path.display());
+                return Err(e.context(context).into());
+            }
+        };
+
+        let answers = toml::from_str(&text)
+            .with_context(|_| format!("Invalid answers file {}", path.display()))?;
+
+        Ok(answers)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        fs::write(path, toml::to_string_pretty(self)?)
+            .with_context(|_| format!("Failed to write answers file {}", path.display()))?;
+        Ok(())
+    }
+
+    pub fn get(&self, day: u32) -> Option<&Recorded> {
+        self.days.iter().find(|d| d.number == day)
+    }
+
+    pub fn record(&mut self, day: u32, part1: &str, part2: &str) {
+        let recorded = Recorded {
+            number: day,
+            part1: part1.to_owned(),
+            part2: part2.to_owned(),
+        };
+
+        match self.days.iter_mut().find(|d| d.number == day) {
+            Some(d) => *d = recorded,
+            None => self.days.push(recorded),
+        }
+
+        self.days.sort_by_key(|d| d.number);
+    }
+}
+
+/// The outcome of comparing one answer against the answers file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Check {
+    Pass,
+    Fail { expected: String, actual: String },
+    Missing,
+}
+
+impl Check {
+    pub fn new(expected: Option<&str>, actual: &str) -> Check {
+        match expected {
+            Some(expected) if expected == actual => Check::Pass,
+            Some(expected) => Check::Fail {
+                expected: expected.to_owned(),
+                actual: actual.to_owned(),
+            },
+            None => Check::Missing,
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        *self == Check::Pass
+    }
+}
@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+use failure::Error;
+
+use crate::solution::{timed, Parts, Solution};
+
+/// Summary of repeated timings of a single stage.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    pub fn new(samples: &[Duration]) -> Stats {
+        let mut samples = samples.to_vec();
+        samples.sort();
+
+        let median = match samples.len() {
+            0 => Duration::default(),
+            n if n % 2 == 1 => samples[n / 2],
+            n => (samples[n / 2 - 1] + samples[n / 2]) / 2,
+        };
+
+        Stats {
+            min: samples.first().cloned().unwrap_or_default(),
+            median,
+            max: samples.last().cloned().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bench {
+    pub iterations: usize,
+    pub parse: Stats,
+    pub part1: Option<Stats>,
+    pub part2: Option<Stats>,
+}
+
+/// Time parsing and each of the selected parts `iterations` times. The parts
+/// all run against the same parsed input.
+pub(crate) fn bench<S: Solution>(
+    input: &str,
+    parts: Parts,
+    iterations: usize,
+) -> Result<Bench, Error> {
+    let mut parse = Vec::new();
+    let mut part1 = Vec::new();
+    let mut part2 = Vec::new();
+
+    let mut solution = None;
+    for _ in 0..iterations {
+        let (result, time) = timed(|| S::parse(input));
+        solution = Some(result?);
+        parse.push(time);
+    }
+
+    if let Some(solution) = solution {
+        if parts.part1() {
+            for _ in 0..iterations {
+                let (result, time) = timed(|| solution.part1());
+                result?;
+                part1.push(time);
+            }
+        }
+
+        if parts.part2() {
+            for _ in 0..iterations {
+                let (result, time) = timed(|| solution.part2());
+                result?;
+                part2.push(time);
+            }
+        }
+    }
+
+    Ok(Bench {
+        iterations,
+        parse: Stats::new(&parse),
+        part1: Some(Stats::new(&part1)).filter(|_| parts.part1()),
+        part2: Some(Stats::new(&part2)).filter(|_| parts.part2()),
+    })
+}
@@ -1,22 +1,72 @@
 // Day 1: Chronal Calibration
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
 
-pub fn solve() {
-    let lines: Vec<i32> = include_str!("data/day1.txt")
-        .lines()
-        .map(|s| s.parse().unwrap())
-        .collect();
+use failure::{err_msg, Error};
 
-    println!("Part 1: {}", part1(&lines));
-    println!("Part 2: {}", part2(&lines));
+use crate::parse::{parse_lines_with, FieldError};
+use crate::solution::Solution;
+
+pub const INPUT: &str = include_str!("data/day1.txt");
+
+pub struct Day1 {
+    pub lines: Vec<i32>,
 }
 
-fn part1(lines: &[i32]) -> i32 {
+impl Solution for Day1 {
+    type Part1 = i32;
+    type Part2 = i32;
+
+    fn parse(input: &str) -> Result<Self, Error> {
+        let lines = parse_lines_with(input, |s| {
+            s.parse().map_err(|e| {
+                FieldError::new(1, format!("a frequency change like `+7` or `-3` ({})", e))
+            })
+        })?;
+
+        Ok(Day1 { lines })
+    }
+
+    fn part1(&self) -> Result<i32, Error> {
+        Ok(part1(&self.lines))
+    }
+
+    fn part2(&self) -> Result<i32, Error> {
+        part2(&self.lines).ok_or_else(|| err_msg("No frequency is ever reached twice"))
+    }
+}
+
+pub fn part1(lines: &[i32]) -> i32 {
     lines.iter().sum()
 }
 
-fn part2(lines: &[i32]) -> i32 {
+/// The first frequency reached twice, or `None` if the frequency never repeats.
+pub fn part2(lines: &[i32]) -> Option<i32> {
+    first_repeat(lines, Method::Analytic)
+}
+
+/// How to search for the first repeated frequency.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    /// Apply the changes pass after pass, remembering every frequency seen.
+    Simulate,
+    /// Work out the first repeat from the frequencies of a single pass.
+    Analytic,
+}
+
+pub fn first_repeat(lines: &[i32], method: Method) -> Option<i32> {
+    match method {
+        Method::Simulate => simulate(lines),
+        Method::Analytic => analytic(lines),
+    }
+}
+
+fn simulate(lines: &[i32]) -> Option<i32> {
+    if !repeats(lines) {
+        return None;
+    }
+
     let mut freq = 0;
 
     let mut seen = HashSet::new();
@@ -25,9 +75,336 @@ fn part2(lines: &[i32]) -> i32 {
     for delta in lines.iter().cycle() {
         freq += delta;
         if !seen.insert(freq) {
-            return freq;
+            return Some(freq);
         }
     }
 
     unreachable!();
 }
+
+/// Pass `k` visits the frequencies of the first pass shifted by `k * drift`,
+/// so frequency `f` at index `i` comes round to an earlier frequency `g` after
+/// `(g - f) / drift` passes, provided that is a positive whole number. Within
+/// each residue class modulo the drift, the nearest frequency in the direction
+/// of the drift is the first one reached.
+fn analytic(lines: &[i32]) -> Option<i32> {
+    if lines.is_empty() {
+        return None;
+    }
+
+    let mut freqs = Vec::with_capacity(lines.len());
+    let mut freq = 0i64;
+    let mut seen = HashSet::new();
+
+    for &delta in lines {
+        if !seen.insert(freq) {
+            return Some(freq as i32);
+        }
+        freqs.push(freq);
+        freq += i64::from(delta);
+    }
+
+    let drift = freq;
+    if drift == 0 {
+        return Some(0);
+    }
+
+    let mut groups: HashMap<i64, Vec<(i64, usize)>> = HashMap::new();
+    for (idx, &f) in freqs.iter().enumerate() {
+        groups
+            .entry(f.rem_euclid(drift.abs()))
+            .or_default()
+            .push((f, idx));
+    }
+
+    let pass_len = lines.len() as i64;
+    let mut repeats = Vec::new();
+
+    for group in groups.values_mut() {
+        group.sort();
+        if drift < 0 {
+            group.reverse();
+        }
+
+        for pair in group.windows(2) {
+            let ((from, idx), (to, _)) = (pair[0], pair[1]);
+            let time = (to - from) / drift * pass_len + idx as i64;
+            repeats.push((time, to));
+        }
+    }
+
+    repeats.into_iter().min().map(|(_, freq)| freq as i32)
+}
+
+/// Whether any frequency is ever reached twice. Each pass shifts every
+/// frequency in it by the total drift, so a frequency can only come round
+/// again if two frequencies within one pass are equal modulo the drift.
+pub fn repeats(lines: &[i32]) -> bool {
+    if lines.is_empty() {
+        return false;
+    }
+
+    let drift: i64 = lines.iter().map(|&d| i64::from(d)).sum();
+    if drift == 0 {
+        return true;
+    }
+
+    let mut freq = 0i64;
+    let mut residues = HashSet::new();
+
+    for &delta in lines {
+        if !residues.insert(freq.rem_euclid(drift.abs())) {
+            return true;
+        }
+        freq += i64::from(delta);
+    }
+
+    false
+}
+
+/// One row of a trace: the frequency after applying change `index` during
+/// pass `pass`, or the starting frequency if there is no change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    /// 1-based pass through the list of changes.
+    pub pass: usize,
+    /// 0-based index of the change within the list.
+    pub index: Option<usize>,
+    pub delta: Option<i32>,
+    pub frequency: i32,
+}
+
+/// The first repeated frequency, as indices into the steps of a trace.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Repeat {
+    pub first_seen: usize,
+    pub repeated: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    pub steps: Vec<Step>,
+    pub repeat: Option<Repeat>,
+}
+
+/// Every frequency up to and including the first one reached twice, or just
+/// the first pass if the frequency never repeats.
+pub fn trace(lines: &[i32]) -> Trace {
+    let mut steps = vec![Step {
+        pass: 1,
+        index: None,
+        delta: None,
+        frequency: 0,
+    }];
+
+    if !repeats(lines) {
+        for (index, &delta) in lines.iter().enumerate() {
+            let frequency = steps[steps.len() - 1].frequency + delta;
+            steps.push(Step {
+                pass: 1,
+                index: Some(index),
+                delta: Some(delta),
+                frequency,
+            });
+        }
+
+        return Trace {
+            steps,
+            repeat: None,
+        };
+    }
+
+    let mut seen = HashMap::new();
+    seen.insert(0, 0);
+
+    for (step, (index, &delta)) in lines.iter().enumerate().cycle().enumerate() {
+        let frequency = steps[step].frequency + delta;
+        steps.push(Step {
+            pass: step / lines.len() + 1,
+            index: Some(index),
+            delta: Some(delta),
+            frequency,
+        });
+
+        if let Some(&first_seen) = seen.get(&frequency) {
+            return Trace {
+                steps,
+                repeat: Some(Repeat {
+                    first_seen,
+                    repeated: step + 1,
+                }),
+            };
+        }
+        seen.insert(frequency, step + 1);
+    }
+
+    unreachable!();
+}
+
+impl Trace {
+    /// Write the trace as CSV, noting where the first repeated frequency was
+    /// first seen and where it came round again.
+    pub fn write_csv(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "pass,index,delta,frequency,repeat")?;
+
+        for (idx, step) in self.steps.iter().enumerate() {
+            let note = match self.repeat {
+                Some(r) if r.first_seen == idx => "first seen".to_owned(),
+                Some(r) if r.repeated == idx => {
+                    let first = &self.steps[r.first_seen];
+                    match first.index {
+                        Some(index) => format!("repeat of pass {} index {}", first.pass, index),
+                        None => "repeat of the starting frequency".to_owned(),
+                    }
+                }
+                _ => String::new(),
+            };
+
+            writeln!(
+                out,
+                "{},{},{},{},{}",
+                step.pass,
+                step.index.map(|i| i.to_string()).unwrap_or_default(),
+                step.delta.map(|d| d.to_string()).unwrap_or_default(),
+                step.frequency,
+                note
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deltas(s: &str) -> Vec<i32> {
+        s.split(", ").map(|d| d.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn part1_examples() {
+        assert_eq!(part1(&deltas("+1, -2, +3, +1")), 3);
+        assert_eq!(part1(&deltas("+1, +1, +1")), 3);
+        assert_eq!(part1(&deltas("+1, +1, -2")), 0);
+        assert_eq!(part1(&deltas("-1, -2, -3")), -6);
+    }
+
+    #[test]
+    fn part2_examples() {
+        assert_eq!(part2(&deltas("+1, -2, +3, +1")), Some(2));
+        assert_eq!(part2(&deltas("+1, -1")), Some(0));
+        assert_eq!(part2(&deltas("+3, +3, +4, -2, -4")), Some(10));
+        assert_eq!(part2(&deltas("-6, +3, +8, +5, -6")), Some(5));
+        assert_eq!(part2(&deltas("+7, +7, -2, -7, -4")), Some(14));
+    }
+
+    #[test]
+    fn part2_never_repeats() {
+        assert_eq!(part2(&deltas("+1, +1")), None);
+        assert_eq!(part2(&deltas("+3, -1")), None);
+        assert_eq!(part2(&deltas("-3, +5")), None);
+        assert_eq!(part2(&deltas("-5")), None);
+        assert_eq!(part2(&[]), None);
+        assert!(Day1::parse("+1\n+1\n").unwrap().part2().is_err());
+    }
+
+    #[test]
+    fn part2_repeats_across_passes() {
+        assert_eq!(part2(&deltas("+2, -1")), Some(2));
+        assert_eq!(part2(&deltas("+0")), Some(0));
+        assert_eq!(part2(&deltas("-3, +4")), Some(0));
+    }
+
+    #[test]
+    fn methods_agree() {
+        let examples = [
+            "+1, -2, +3, +1",
+            "+3, +3, +4, -2, -4",
+            "-6, +3, +8, +5, -6",
+            "+7, +7, -2, -7, -4",
+            "+2, -1",
+            "-3, +4",
+            "+1, +1",
+            "+10, -7, +1, -9, +12",
+        ];
+        for example in &examples {
+            let lines = deltas(example);
+            assert_eq!(
+                first_repeat(&lines, Method::Analytic),
+                first_repeat(&lines, Method::Simulate),
+                "{}",
+                example
+            );
+        }
+
+        // A deterministic spread of small inputs covering both signs of drift.
+        let mut seed = 12345u32;
+        for _ in 0..500 {
+            let mut lines = Vec::new();
+            for _ in 0..1 + seed % 8 {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                lines.push((seed >> 16) as i32 % 21 - 10);
+            }
+            assert_eq!(
+                first_repeat(&lines, Method::Analytic),
+                first_repeat(&lines, Method::Simulate),
+                "{:?}",
+                lines
+            );
+        }
+    }
+
+    #[test]
+    fn trace_marks_first_repeat() {
+        let example = trace(&deltas("+1, -2, +3, +1"));
+        assert_eq!(example.steps.len(), 7);
+        assert_eq!(
+            example.repeat,
+            Some(Repeat {
+                first_seen: 3,
+                repeated: 6
+            })
+        );
+        assert_eq!(example.steps[6].frequency, 2);
+
+        let mut csv = Vec::new();
+        example.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "pass,index,delta,frequency,repeat\n\
+             1,,,0,\n\
+             1,0,1,1,\n\
+             1,1,-2,-1,\n\
+             1,2,3,2,first seen\n\
+             1,3,1,3,\n\
+             2,0,1,4,\n\
+             2,1,-2,2,repeat of pass 1 index 2\n"
+        );
+    }
+
+    #[test]
+    fn trace_without_repeat() {
+        let drifting = trace(&deltas("+1, +1"));
+        assert_eq!(drifting.repeat, None);
+        assert_eq!(drifting.steps.len(), 3);
+
+        let returning = trace(&deltas("+1, -1"));
+        assert_eq!(
+            returning.repeat,
+            Some(Repeat {
+                first_seen: 0,
+                repeated: 2
+            })
+        );
+    }
+
+    #[test]
+    fn real_input() {
+        let day = Day1::parse(INPUT).unwrap();
+        assert_eq!(day.part1().unwrap(), 590);
+        assert_eq!(day.part2().unwrap(), 83445);
+        assert_eq!(first_repeat(&day.lines, Method::Simulate), Some(83445));
+    }
+}
@@ -2,24 +2,56 @@ use std::ops::{Add, AddAssign, Sub};
 use std::str::FromStr;
 
 use failure::{err_msg, Error};
-use regex::Regex;
 
-pub fn solve() {
-    let lines: Vec<Light> = include_str!("data/day10.txt")
-        .lines()
-        .map(|s| s.parse().unwrap())
-        .collect();
+use crate::parse::{parse_lines, FieldError, Pattern};
+use crate::solution::{Answer, Solution};
 
-    let mut sky = Sky::new(&lines);
+pub const INPUT: &str = include_str!("data/day10.txt");
+
+pub struct Day10 {
+    pub lines: Vec<Light>,
+}
+
+impl Solution for Day10 {
+    type Part1 = Answer;
+    type Part2 = u32;
+
+    fn parse(input: &str) -> Result<Self, Error> {
+        let lines = parse_lines(input)?;
+
+        Ok(Day10 { lines })
+    }
+
+    fn part1(&self) -> Result<Answer, Error> {
+        let (sky, _) = converge(&self.lines).ok_or_else(no_lights)?;
+        let rows = sky.render();
+        let text = recognize(&rows);
+        Ok(Answer::Picture { rows, text })
+    }
+
+    fn part2(&self) -> Result<u32, Error> {
+        let (_, t) = converge(&self.lines).ok_or_else(no_lights)?;
+        Ok(t)
+    }
+}
+
+fn no_lights() -> Error {
+    err_msg("There are no lights in the input")
+}
+
+/// The sky at the moment its lights are closest together, and how many
+/// seconds that takes, or `None` if there are no lights.
+pub fn converge(lines: &[Light]) -> Option<(Sky, u32)> {
+    let mut sky = Sky::new(lines);
 
     let mut min_t = 0;
     let mut min_sky = sky.clone();
-    let mut min_cost = min_sky.cost();
+    let mut min_cost = min_sky.cost()?;
 
     for t in 1.. {
         sky.step();
 
-        let cost = sky.cost();
+        let cost = sky.cost()?;
         if cost < min_cost {
             min_t = t;
             min_sky = sky.clone();
@@ -29,47 +61,47 @@ pub fn solve() {
         }
     }
 
-    println!("Part 1:");
-    min_sky.print();
-
-    println!("Part 2: {}", min_t);
+    Some((min_sky, min_t))
 }
 
 #[derive(Clone)]
-struct Sky {
-    lights: Vec<Light>,
+pub struct Sky {
+    pub lights: Vec<Light>,
 }
 
 impl Sky {
-    fn new(lights: &[Light]) -> Sky {
+    pub fn new(lights: &[Light]) -> Sky {
         Sky {
             lights: lights.to_owned().to_vec(),
         }
     }
 
-    fn step(&mut self) {
+    pub fn step(&mut self) {
         for light in &mut self.lights {
             light.p += light.v;
         }
     }
 
     // I guess all the points will be close together at the right time?
-    fn cost(&self) -> i32 {
-        let (_, shape) = self.bounding_box();
-        shape.x + shape.y
+    pub fn cost(&self) -> Option<i32> {
+        let (_, shape) = self.bounding_box()?;
+        Some(shape.x + shape.y)
     }
 
-    fn bounding_box(&self) -> (Vec2, Vec2) {
-        let minx = self.lights.iter().map(|l| l.p.x).min().unwrap();
-        let maxx = self.lights.iter().map(|l| l.p.x).max().unwrap();
-        let miny = self.lights.iter().map(|l| l.p.y).min().unwrap();
-        let maxy = self.lights.iter().map(|l| l.p.y).max().unwrap();
+    pub fn bounding_box(&self) -> Option<(Vec2, Vec2)> {
+        let minx = self.lights.iter().map(|l| l.p.x).min()?;
+        let maxx = self.lights.iter().map(|l| l.p.x).max()?;
+        let miny = self.lights.iter().map(|l| l.p.y).min()?;
+        let maxy = self.lights.iter().map(|l| l.p.y).max()?;
 
-        (Vec2::new(minx, miny), Vec2::new(maxx - minx, maxy - miny))
+        Some((Vec2::new(minx, miny), Vec2::new(maxx - minx, maxy - miny)))
     }
 
-    fn print(&self) {
-        let (corner, shape) = self.bounding_box();
+    pub fn render(&self) -> Vec<String> {
+        let (corner, shape) = match self.bounding_box() {
+            Some(bounds) => bounds,
+            None => return Vec::new(),
+        };
 
         let mut pixels = vec![vec![' '; shape.x as usize + 1]; shape.y as usize + 1];
 
@@ -78,20 +110,209 @@ impl Sky {
             pixels[p.y as usize][p.x as usize] = '+';
         }
 
-        for row in pixels {
-            println!("{}", row.into_iter().collect::<String>());
-        }
+        pixels
+            .into_iter()
+            .map(|row| row.into_iter().collect())
+            .collect()
     }
 }
 
+const GLYPH_WIDTH: usize = 6;
+const GLYPH_HEIGHT: usize = 10;
+const GLYPH_SPACING: usize = 2;
+
+// The letters the lights are known to spell, '#' for a light and '.' for dark.
+#[rustfmt::skip]
+const FONT: &[(char, [&str; GLYPH_HEIGHT])] = &[
+    ('A', ["..##..",
+           ".#..#.",
+           "#....#",
+           "#....#",
+           "#....#",
+           "######",
+           "#....#",
+           "#....#",
+           "#....#",
+           "#....#"]),
+    ('B', ["#####.",
+           "#....#",
+           "#....#",
+           "#....#",
+           "#####.",
+           "#....#",
+           "#....#",
+           "#....#",
+           "#....#",
+           "#####."]),
+    ('C', [".####.",
+           "#....#",
+           "#.....",
+           "#.....",
+           "#.....",
+           "#.....",
+           "#.....",
+           "#.....",
+           "#....#",
+           ".####."]),
+    ('E', ["######",
+           "#.....",
+           "#.....",
+           "#.....",
+           "#####.",
+           "#.....",
+           "#.....",
+           "#.....",
+           "#.....",
+           "######"]),
+    ('F', ["######",
+           "#.....",
+           "#.....",
+           "#.....",
+           "#####.",
+           "#.....",
+           "#.....",
+           "#.....",
+           "#.....",
+           "#....."]),
+    ('G', [".####.",
+           "#....#",
+           "#.....",
+           "#.....",
+           "#.....",
+           "#..###",
+           "#....#",
+           "#....#",
+           "#...##",
+           ".###.#"]),
+    ('H', ["#....#",
+           "#....#",
+           "#....#",
+           "#....#",
+           "######",
+           "#....#",
+           "#....#",
+           "#....#",
+           "#....#",
+           "#....#"]),
+    ('J', ["...###",
+           "....#.",
+           "....#.",
+           "....#.",
+           "....#.",
+           "....#.",
+           "....#.",
+           "#...#.",
+           "#...#.",
+           ".###.."]),
+    ('K', ["#....#",
+           "#...#.",
+           "#..#..",
+           "#.#...",
+           "##....",
+           "##....",
+           "#.#...",
+           "#..#..",
+           "#...#.",
+           "#....#"]),
+    ('L', ["#.....",
+           "#.....",
+           "#.....",
+           "#.....",
+           "#.....",
+           "#.....",
+           "#.....",
+           "#.....",
+           "#.....",
+           "######"]),
+    ('N', ["#....#",
+           "##...#",
+           "##...#",
+           "#.#..#",
+           "#.#..#",
+           "#..#.#",
+           "#..#.#",
+           "#...##",
+           "#...##",
+           "#....#"]),
+    ('P', ["#####.",
+           "#....#",
+           "#....#",
+           "#....#",
+           "#####.",
+           "#.....",
+           "#.....",
+           "#.....",
+           "#.....",
+           "#....."]),
+    ('R', ["#####.",
+           "#....#",
+           "#....#",
+           "#....#",
+           "#####.",
+           "#..#..",
+           "#...#.",
+           "#...#.",
+           "#....#",
+           "#....#"]),
+    ('X', ["#....#",
+           "#....#",
+           ".#..#.",
+           ".#..#.",
+           "..##..",
+           "..##..",
+           ".#..#.",
+           ".#..#.",
+           "#....#",
+           "#....#"]),
+    ('Z', ["######",
+           ".....#",
+           ".....#",
+           "....#.",
+           "...#..",
+           "..#...",
+           ".#....",
+           "#.....",
+           "#.....",
+           "######"]),
+];
+
+/// Read the letters spelled out by a rendered sky, using '?' for anything
+/// that isn't in the font.
+pub fn recognize(rows: &[String]) -> String {
+    if rows.len() != GLYPH_HEIGHT {
+        return "?".to_owned();
+    }
+
+    let rows: Vec<Vec<char>> = rows.iter().map(|r| r.chars().collect()).collect();
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let stride = GLYPH_WIDTH + GLYPH_SPACING;
+
+    (0..(width + GLYPH_SPACING) / stride)
+        .map(|idx| {
+            let glyph: Vec<String> = rows
+                .iter()
+                .map(|row| {
+                    (idx * stride..idx * stride + GLYPH_WIDTH)
+                        .map(|x| if row.get(x) == Some(&'+') { '#' } else { '.' })
+                        .collect()
+                })
+                .collect();
+
+            FONT.iter()
+                .find(|(_, pattern)| pattern.iter().zip(&glyph).all(|(p, g)| p == g))
+                .map_or('?', |(c, _)| *c)
+        })
+        .collect()
+}
+
 #[derive(Debug, Copy, Clone)]
-struct Vec2 {
-    x: i32,
-    y: i32,
+pub struct Vec2 {
+    pub x: i32,
+    pub y: i32,
 }
 
 impl Vec2 {
-    fn new(x: i32, y: i32) -> Vec2 {
+    pub fn new(x: i32, y: i32) -> Vec2 {
         Vec2 { x, y }
     }
 }
@@ -125,36 +346,121 @@ impl Sub for Vec2 {
 }
 
 #[derive(Debug, Clone)]
-struct Light {
-    p: Vec2,
-    v: Vec2,
+pub struct Light {
+    pub p: Vec2,
+    pub v: Vec2,
 }
 
 impl Light {
-    fn new(p: Vec2, v: Vec2) -> Light {
+    pub fn new(p: Vec2, v: Vec2) -> Light {
         Light { p, v }
     }
 }
 
+static LIGHT: Pattern = Pattern::new(&[
+    ("position=<", "`position=<`"),
+    (r"\s*(-?\d+)", "the x position"),
+    (",", "`,`"),
+    (r"\s*(-?\d+)", "the y position"),
+    ("> velocity=<", "`> velocity=<`"),
+    (r"\s*(-?\d+)", "the x velocity"),
+    (",", "`,`"),
+    (r"\s*(-?\d+)", "the y velocity"),
+    (">", "`>`"),
+]);
+
 impl FromStr for Light {
-    type Err = Error;
+    type Err = FieldError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let re = Regex::new(r"position=<\s*(-?\d+),\s*(-?\d+)> velocity=<\s*(-?\d+),\s*(-?\d+)>")
-            .unwrap();
-        let caps = re.captures(s).ok_or_else(|| err_msg("Invalid format"))?;
-
-        let get = |idx| -> Result<i32, Self::Err> {
-            Ok(caps
-                .get(idx)
-                .ok_or_else(|| err_msg("Invalid format"))?
-                .as_str()
-                .parse()?)
-        };
+        let fields = LIGHT.captures(s)?;
 
         Ok(Light::new(
-            Vec2::new(get(1)?, get(2)?),
-            Vec2::new(get(3)?, get(4)?),
+            Vec2::new(fields.get(1)?, fields.get(2)?),
+            Vec2::new(fields.get(3)?, fields.get(4)?),
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+position=< 9,  1> velocity=< 0,  2>
+position=< 7,  0> velocity=<-1,  0>
+position=< 3, -2> velocity=<-1,  1>
+position=< 6, 10> velocity=<-2, -1>
+position=< 2, -4> velocity=< 2,  2>
+position=<-6, 10> velocity=< 2, -2>
+position=< 1,  8> velocity=< 1, -1>
+position=< 1,  7> velocity=< 1,  0>
+position=<-3, 11> velocity=< 1, -2>
+position=< 7,  6> velocity=<-1, -1>
+position=<-2,  3> velocity=< 1,  0>
+position=<-4,  3> velocity=< 2,  0>
+position=<10, -3> velocity=<-1,  1>
+position=< 5, 11> velocity=< 1, -2>
+position=< 4,  7> velocity=< 0, -1>
+position=< 8, -2> velocity=< 0,  1>
+position=<15,  0> velocity=<-2,  0>
+position=< 1,  6> velocity=< 1,  0>
+position=< 8,  9> velocity=< 0, -1>
+position=< 3,  3> velocity=<-1,  1>
+position=< 0,  5> velocity=< 0, -1>
+position=<-2,  2> velocity=< 2,  0>
+position=< 5, -2> velocity=< 1,  2>
+position=< 1,  4> velocity=< 2,  1>
+position=<-2,  7> velocity=< 2, -2>
+position=< 3,  6> velocity=<-1, -1>
+position=< 5,  0> velocity=< 1,  0>
+position=<-6,  0> velocity=< 2,  0>
+position=< 5,  9> velocity=< 1, -2>
+position=<14,  7> velocity=<-2,  0>
+position=<-3,  6> velocity=< 2, -1>
+";
+
+    #[test]
+    fn example() {
+        let day = Day10::parse(EXAMPLE).unwrap();
+        let (sky, t) = converge(&day.lines).unwrap();
+
+        assert_eq!(t, 3);
+        assert_eq!(
+            sky.render(),
+            vec![
+                "+   +  +++",
+                "+   +   + ",
+                "+   +   + ",
+                "+++++   + ",
+                "+   +   + ",
+                "+   +   + ",
+                "+   +   + ",
+                "+   +  +++",
+            ]
+        );
+    }
+
+    #[test]
+    fn no_lights() {
+        let day = Day10::parse("").unwrap();
+        assert!(day.part1().is_err());
+        assert!(day.part2().is_err());
+    }
+
+    #[test]
+    fn real_input() {
+        let day = Day10::parse(INPUT).unwrap();
+
+        let answer = day.part1().unwrap();
+        assert_eq!(answer.recorded(), "RRANZLAC");
+        match answer {
+            Answer::Picture { rows, text } => {
+                assert_eq!(rows.len(), 10);
+                assert_eq!(text, "RRANZLAC");
+            }
+            answer => panic!("expected a picture, got {:?}", answer),
+        }
+        assert_eq!(day.part2().unwrap(), 10942);
+    }
+}
@@ -1,49 +1,323 @@
 // Day 2: Inventory Management System
 
-pub fn solve() {
-    let lines: Vec<&str> = include_str!("data/day2.txt").lines().collect();
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Display};
 
-    println!("Part 1: {}", part1(&lines));
-    println!("Part 2: {}", part2(&lines));
+use failure::{err_msg, Error};
+
+use crate::solution::Solution;
+
+pub const INPUT: &str = include_str!("data/day2.txt");
+
+pub struct Day2 {
+    pub lines: Vec<String>,
+}
+
+impl Solution for Day2 {
+    type Part1 = u64;
+    type Part2 = String;
+
+    fn parse(input: &str) -> Result<Self, Error> {
+        let lines = input.lines().map(str::to_owned).collect();
+
+        Ok(Day2 { lines })
+    }
+
+    fn part1(&self) -> Result<u64, Error> {
+        Ok(part1(&self.lines))
+    }
+
+    fn part2(&self) -> Result<String, Error> {
+        part2(&self.lines).ok_or_else(|| err_msg("No two box IDs differ by exactly one letter"))
+    }
+}
+
+pub fn part1(lines: &[String]) -> u64 {
+    checksum(lines, &[2, 3])
+}
+
+/// For each of `repeats`, count the IDs containing some letter exactly that
+/// many times, and multiply the counts together.
+pub fn checksum(ids: &[String], repeats: &[u32]) -> u64 {
+    let reports: Vec<LetterCounts> = ids.iter().map(|id| LetterCounts::new(id)).collect();
+
+    repeats
+        .iter()
+        .map(|&n| reports.iter().filter(|r| r.has_repeat(n)).count() as u64)
+        .product()
+}
+
+/// How many times each letter appears in one ID.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LetterCounts {
+    pub counts: BTreeMap<char, u32>,
+}
+
+impl LetterCounts {
+    pub fn new(id: &str) -> LetterCounts {
+        let mut counts = BTreeMap::new();
+        for letter in id.chars() {
+            *counts.entry(letter).or_insert(0) += 1;
+        }
+
+        LetterCounts { counts }
+    }
+
+    /// Whether some letter appears exactly `n` times.
+    pub fn has_repeat(&self, n: u32) -> bool {
+        self.counts.values().any(|&count| count == n)
+    }
+
+    /// The letters that appear exactly `n` times, in order.
+    pub fn letters(&self, n: u32) -> Vec<char> {
+        self.counts
+            .iter()
+            .filter(|&(_, &count)| count == n)
+            .map(|(&letter, _)| letter)
+            .collect()
+    }
+}
+
+impl Display for LetterCounts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let counts: Vec<String> = self
+            .counts
+            .iter()
+            .map(|(letter, count)| format!("{}={}", letter, count))
+            .collect();
+        write!(f, "{}", counts.join(" "))
+    }
+}
+
+/// The letters shared by the two box IDs that differ in exactly one position,
+/// or `None` if there are no such IDs.
+pub fn part2(lines: &[String]) -> Option<String> {
+    near_matches(lines, 1)
+        .into_iter()
+        .find(|m| m.positions.len() == 1)
+        .map(|m| {
+            lines[m.first]
+                .chars()
+                .enumerate()
+                .filter(|&(idx, _)| idx != m.positions[0])
+                .map(|(_, c)| c)
+                .collect()
+        })
+}
+
+/// Two IDs of the same length that differ in at most `k` positions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NearMatch {
+    /// Index of the first ID, always less than `second`.
+    pub first: usize,
+    pub second: usize,
+    /// 0-based character positions at which the IDs differ.
+    pub positions: Vec<usize>,
+}
+
+/// Every pair of IDs within Hamming distance `k` of each other, in order of
+/// their indices. IDs of different lengths never match.
+///
+/// Exact duplicates and IDs one character apart are found by hashing, so only
+/// IDs that actually match are compared. Larger distances compare every pair
+/// of IDs of the same length.
+pub fn near_matches(ids: &[String], k: usize) -> Vec<NearMatch> {
+    let ids: Vec<Vec<char>> = ids.iter().map(|id| id.chars().collect()).collect();
+
+    let mut matches = match k {
+        0 => duplicates(&ids),
+        1 => {
+            let mut matches = duplicates(&ids);
+            matches.extend(one_apart(&ids));
+            matches
+        }
+        _ => pairwise(&ids, k),
+    };
+
+    matches.sort_by_key(|m| (m.first, m.second));
+    matches
 }
 
-fn part1(lines: &[&str]) -> u32 {
-    let mut dup2 = 0u32;
-    let mut dup3 = 0u32;
+/// Every pair of indices within each group.
+fn pairs(groups: impl IntoIterator<Item = Vec<usize>>) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
 
-    for id in lines {
-        let mut counts = [0u32; 256];
-        for letter in id.bytes() {
-            counts[letter as usize] += 1;
+    for group in groups {
+        for (i, &first) in group.iter().enumerate() {
+            for &second in &group[i + 1..] {
+                pairs.push((first, second));
+            }
         }
+    }
+
+    pairs
+}
+
+fn duplicates(ids: &[Vec<char>]) -> Vec<NearMatch> {
+    let mut groups: HashMap<&[char], Vec<usize>> = HashMap::new();
+    for (idx, id) in ids.iter().enumerate() {
+        groups.entry(id).or_default().push(idx);
+    }
+
+    pairs(groups.into_values())
+        .into_iter()
+        .map(|(first, second)| NearMatch {
+            first,
+            second,
+            positions: Vec::new(),
+        })
+        .collect()
+}
+
+/// IDs that are equal once the character at some position is deleted from
+/// both. Each such pair differs at that position alone, unless the IDs are
+/// exact duplicates.
+fn one_apart(ids: &[Vec<char>]) -> Vec<NearMatch> {
+    let mut groups: HashMap<(usize, Vec<char>), Vec<usize>> = HashMap::new();
 
-        if counts.contains(&2) {
-            dup2 += 1;
+    for (idx, id) in ids.iter().enumerate() {
+        for pos in 0..id.len() {
+            let mut deleted = id.clone();
+            deleted.remove(pos);
+            groups.entry((pos, deleted)).or_default().push(idx);
         }
-        if counts.contains(&3) {
-            dup3 += 1;
+    }
+
+    let mut matches = Vec::new();
+
+    for ((pos, _), group) in groups {
+        for (first, second) in pairs(Some(group)) {
+            if ids[first][pos] != ids[second][pos] {
+                matches.push(NearMatch {
+                    first,
+                    second,
+                    positions: vec![pos],
+                });
+            }
         }
     }
 
-    dup2 * dup3
+    matches
 }
 
-fn part2(lines: &[&str]) -> String {
-    for i in 0..lines.len() {
-        for j in (i + 1)..lines.len() {
-            let (id1, id2) = (lines[i], lines[j]);
+fn pairwise(ids: &[Vec<char>], k: usize) -> Vec<NearMatch> {
+    let mut matches = Vec::new();
 
-            let matching: String = id1
-                .chars()
-                .zip(id2.chars())
-                .filter_map(|(c1, c2)| if c1 == c2 { Some(c1) } else { None })
+    for (first, id1) in ids.iter().enumerate() {
+        for (second, id2) in ids.iter().enumerate().skip(first + 1) {
+            if id1.len() != id2.len() {
+                continue;
+            }
+
+            let positions: Vec<usize> = (0..id1.len())
+                .filter(|&pos| id1[pos] != id2[pos])
+                .take(k + 1)
                 .collect();
 
-            if matching.len() + 1 == id1.len() {
-                return matching;
+            if positions.len() <= k {
+                matches.push(NearMatch {
+                    first,
+                    second,
+                    positions,
+                });
             }
         }
     }
 
-    String::new()
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(s: &str) -> Vec<String> {
+        s.split_whitespace().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn part1_example() {
+        let ids = ids("abcdef bababc abbcde abcccd aabcdd abcdee ababab");
+        assert_eq!(part1(&ids), 12);
+    }
+
+    #[test]
+    fn checksum_counts_chars() {
+        let ids = ids("ééa ñññb ab aéé");
+        assert_eq!(checksum(&ids, &[2, 3]), 2);
+        assert_eq!(checksum(&ids, &[1]), 4);
+        assert_eq!(checksum(&ids, &[4]), 0);
+        assert_eq!(checksum(&ids, &[]), 1);
+
+        let report = LetterCounts::new("ñññbab");
+        assert!(report.has_repeat(3));
+        assert_eq!(report.letters(2), vec!['b']);
+        assert_eq!(report.to_string(), "a=1 b=2 ñ=3");
+    }
+
+    #[test]
+    fn part2_example() {
+        let ids = ids("abcde fghij klmno pqrst fguij axcye wvxyz");
+        assert_eq!(part2(&ids), Some("fgij".to_owned()));
+    }
+
+    #[test]
+    fn part2_no_match() {
+        assert_eq!(part2(&ids("abc abc xyz")), None);
+        assert!(Day2::parse("abcd\nwxyz\n").unwrap().part2().is_err());
+    }
+
+    #[test]
+    fn near_matches_within_k() {
+        let ids = ids("abcd abce abcd xbcy ab abdd");
+
+        let one: Vec<_> = near_matches(&ids, 1)
+            .into_iter()
+            .map(|m| (m.first, m.second, m.positions))
+            .collect();
+        assert_eq!(
+            one,
+            vec![
+                (0, 1, vec![3]),
+                (0, 2, vec![]),
+                (0, 5, vec![2]),
+                (1, 2, vec![3]),
+                (2, 5, vec![2]),
+            ]
+        );
+
+        let two = near_matches(&ids, 2);
+        assert_eq!(two.len(), 9);
+        assert!(two.contains(&NearMatch {
+            first: 0,
+            second: 3,
+            positions: vec![0, 3],
+        }));
+
+        assert_eq!(near_matches(&ids, 0).len(), 1);
+        assert!(near_matches(&[], 1).is_empty());
+    }
+
+    #[test]
+    fn hashing_agrees_with_pairwise() {
+        let ids: Vec<Vec<char>> = Day2::parse(INPUT)
+            .unwrap()
+            .lines
+            .iter()
+            .map(|id| id.chars().collect())
+            .collect();
+
+        let mut hashed = one_apart(&ids);
+        hashed.extend(duplicates(&ids));
+        hashed.sort_by_key(|m| (m.first, m.second));
+        assert_eq!(hashed, pairwise(&ids, 1));
+    }
+
+    #[test]
+    fn real_input() {
+        let day = Day2::parse(INPUT).unwrap();
+        assert_eq!(day.part1().unwrap(), 5456);
+        assert_eq!(day.part2().unwrap(), "megsdlpulxvinkatfoyzxcbvq");
+        assert_eq!(near_matches(&day.lines, 1).len(), 1);
+    }
 }
@@ -1,82 +1,413 @@
-use std::ops::{Index, IndexMut};
 use std::str::FromStr;
 
 use failure::{err_msg, Error};
-use regex::Regex;
+use serde_derive::Serialize;
 
-pub fn solve() {
-    let lines: Vec<Claim> = include_str!("data/day3.txt")
-        .lines()
-        .map(|s| s.parse().unwrap())
-        .collect();
+use crate::image::{Image, Rgb};
+use crate::parse::{parse_lines, FieldError, Pattern};
+use crate::solution::Solution;
+
+pub const INPUT: &str = include_str!("data/day3.txt");
+
+pub struct Day3 {
+    pub lines: Vec<Claim>,
+}
+
+impl Solution for Day3 {
+    type Part1 = u64;
+    type Part2 = u32;
+
+    fn parse(input: &str) -> Result<Self, Error> {
+        let lines = parse_lines(input)?;
+
+        Ok(Day3 { lines })
+    }
+
+    fn part1(&self) -> Result<u64, Error> {
+        Ok(part1(&self.lines))
+    }
+
+    fn part2(&self) -> Result<u32, Error> {
+        part2(&self.lines).ok_or_else(|| err_msg("Every claim overlaps another claim"))
+    }
+}
+
+pub fn part1(lines: &[Claim]) -> u64 {
+    contested(lines, Engine::choose(lines))
+}
+
+/// The id of the first claim that doesn't overlap any other, or `None` if
+/// every claim overlaps another.
+pub fn part2(lines: &[Claim]) -> Option<u32> {
+    uncontested(lines, Engine::choose(lines))
+        .first()
+        .map(|claim| claim.id)
+}
+
+/// The largest fabric the dense engine will allocate, in square inches.
+const DENSE_LIMIT: u64 = 1 << 24;
+
+/// How to work out which squares of fabric are claimed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Engine {
+    /// Count the claims on every square of a `Cloth`.
+    Dense,
+    /// Sweep across the claims' edges, so the cost depends on the number of
+    /// claims rather than the size of the fabric.
+    Sweep,
+}
+
+impl Engine {
+    /// The dense engine if the fabric is small enough to allocate, otherwise
+    /// the sweep.
+    pub fn choose(claims: &[Claim]) -> Engine {
+        let (width, height) = extent(claims);
+        match width.checked_mul(height) {
+            Some(area) if area <= DENSE_LIMIT && width.max(height) <= DENSE_LIMIT => Engine::Dense,
+            _ => Engine::Sweep,
+        }
+    }
+}
 
-    println!("Part 1: {}", part1(&lines));
-    println!("Part 2: {}", part2(&lines));
+/// The width and height of fabric needed to hold every claim. Claims with no
+/// area don't cover any squares, so they don't need any fabric.
+fn extent(claims: &[Claim]) -> (u64, u64) {
+    let claims = claims.iter().filter(|c| c.area() > 0);
+    let width = claims.clone().map(Claim::right).max().unwrap_or(0);
+    let height = claims.map(Claim::bottom).max().unwrap_or(0);
+    (width, height)
 }
 
-fn part1(lines: &[Claim]) -> u32 {
-    let width = lines.iter().map(|c| c.left + c.width).max().unwrap();
-    let height = lines.iter().map(|c| c.top + c.height).max().unwrap();
-    let mut cloth = Cloth::new(width as usize, height as usize);
+/// The number of square inches within two or more claims.
+pub fn contested(claims: &[Claim], engine: Engine) -> u64 {
+    match engine {
+        Engine::Dense => Cloth::covering(claims).contested(),
+        Engine::Sweep => sweep_contested(claims),
+    }
+}
 
-    for claim in lines {
-        cloth.raster(claim);
+/// The claims that don't overlap any other claim, in input order.
+pub fn uncontested(claims: &[Claim], engine: Engine) -> Vec<&Claim> {
+    match engine {
+        Engine::Dense => {
+            let cloth = Cloth::covering(claims);
+            claims.iter().filter(|c| cloth.check(c)).collect()
+        }
+        Engine::Sweep => {
+            let mut contested = vec![false; claims.len()];
+            for (a, b) in overlapping(claims) {
+                contested[a] = true;
+                contested[b] = true;
+            }
+
+            claims
+                .iter()
+                .zip(contested)
+                .filter(|&(_, contested)| !contested)
+                .map(|(claim, _)| claim)
+                .collect()
+        }
     }
+}
+
+/// Every pair of claims that overlap, as indices into `claims`. Claims are
+/// taken in order of their left edges, so each is only compared against the
+/// claims that start before it ends.
+pub fn overlapping(claims: &[Claim]) -> Vec<(usize, usize)> {
+    let mut order: Vec<usize> = (0..claims.len()).collect();
+    order.sort_by_key(|&idx| claims[idx].left);
+
+    let mut pairs = Vec::new();
+
+    for (pos, &a) in order.iter().enumerate() {
+        for &b in &order[pos + 1..] {
+            if u64::from(claims[b].left) >= claims[a].right() {
+                break;
+            }
+            if claims[a].overlap(&claims[b]) > 0 {
+                pairs.push((a.min(b), a.max(b)));
+            }
+        }
+    }
+
+    pairs.sort();
+    pairs
+}
+
+/// Which claims overlap each other, and by how much.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OverlapReport {
+    pub claims: Vec<ClaimOverlaps>,
+    /// The ids of the claims that don't overlap any other, in input order.
+    pub uncontested: Vec<u32>,
+}
 
-    cloth.contested()
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClaimOverlaps {
+    pub id: u32,
+    pub overlaps: Vec<Overlap>,
 }
 
-fn part2(lines: &[Claim]) -> u32 {
-    let width = lines.iter().map(|c| c.left + c.width).max().unwrap();
-    let height = lines.iter().map(|c| c.top + c.height).max().unwrap();
-    let mut cloth = Cloth::new(width as usize, height as usize);
+/// Another claim overlapping this one, and the square inches they share.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Overlap {
+    pub id: u32,
+    pub area: u64,
+}
+
+impl OverlapReport {
+    pub fn new(claims: &[Claim]) -> OverlapReport {
+        let mut overlaps = vec![Vec::new(); claims.len()];
+
+        for (a, b) in overlapping(claims) {
+            let area = claims[a].overlap(&claims[b]);
+            overlaps[a].push(Overlap {
+                id: claims[b].id,
+                area,
+            });
+            overlaps[b].push(Overlap {
+                id: claims[a].id,
+                area,
+            });
+        }
+
+        let uncontested = claims
+            .iter()
+            .zip(&overlaps)
+            .filter(|(_, overlaps)| overlaps.is_empty())
+            .map(|(claim, _)| claim.id)
+            .collect();
+
+        let claims = claims
+            .iter()
+            .zip(overlaps)
+            .map(|(claim, overlaps)| ClaimOverlaps {
+                id: claim.id,
+                overlaps,
+            })
+            .collect();
 
-    for claim in lines {
-        cloth.raster(claim);
+        OverlapReport {
+            claims,
+            uncontested,
+        }
     }
+}
+
+/// Sweep a vertical line across the fabric, tracking how many claims cover
+/// each band between consecutive top and bottom edges.
+fn sweep_contested(claims: &[Claim]) -> u64 {
+    let mut edges: Vec<u64> = claims
+        .iter()
+        .flat_map(|c| vec![u64::from(c.top), c.bottom()])
+        .collect();
+    edges.sort();
+    edges.dedup();
+
+    let band = |y: u64| edges.binary_search(&y).unwrap();
+
+    let mut events = Vec::new();
+    for claim in claims.iter().filter(|c| c.area() > 0) {
+        let (top, bottom) = (band(u64::from(claim.top)), band(claim.bottom()));
+        events.push((u64::from(claim.left), top, bottom, 1));
+        events.push((claim.right(), top, bottom, -1));
+    }
+    events.sort();
+
+    let mut bands = Bands::new(&edges);
+    let mut contested = 0;
+    let mut prev_x = 0;
+
+    for (x, top, bottom, delta) in events {
+        contested += (x - prev_x) * bands.contested();
+        prev_x = x;
+
+        bands.add(top, bottom, delta);
+    }
+
+    contested
+}
+
+/// A segment tree over the bands between consecutive `edges`, which keeps
+/// track of the total height covered by at least two claims.
+struct Bands<'a> {
+    edges: &'a [u64],
+    /// The number of claims covering the whole of each node's range that
+    /// haven't been counted by an ancestor.
+    cover: Vec<i32>,
+    /// The height within each node's range covered at least once.
+    once: Vec<u64>,
+    /// The height within each node's range covered at least twice.
+    twice: Vec<u64>,
+}
 
-    for claim in lines {
-        if cloth.check(claim) {
-            return claim.id;
+impl<'a> Bands<'a> {
+    fn new(edges: &'a [u64]) -> Bands<'a> {
+        let nodes = 4 * edges.len().max(1);
+        Bands {
+            edges,
+            cover: vec![0; nodes],
+            once: vec![0; nodes],
+            twice: vec![0; nodes],
         }
     }
 
-    0
+    fn contested(&self) -> u64 {
+        self.twice[1]
+    }
+
+    /// Add `delta` claims to the bands from `top` up to `bottom`.
+    fn add(&mut self, top: usize, bottom: usize, delta: i32) {
+        let bands = self.edges.len().saturating_sub(1);
+        self.update(1, 0, bands, top, bottom, delta);
+    }
+
+    fn update(&mut self, node: usize, lo: usize, hi: usize, top: usize, bottom: usize, delta: i32) {
+        if bottom <= lo || hi <= top {
+            return;
+        }
+
+        if top <= lo && hi <= bottom {
+            self.cover[node] += delta;
+        } else {
+            let mid = (lo + hi) / 2;
+            self.update(2 * node, lo, mid, top, bottom, delta);
+            self.update(2 * node + 1, mid, hi, top, bottom, delta);
+        }
+
+        let height = self.edges[hi] - self.edges[lo];
+        let leaf = hi - lo == 1;
+        let (once, twice) = if leaf {
+            (0, 0)
+        } else {
+            (
+                self.once[2 * node] + self.once[2 * node + 1],
+                self.twice[2 * node] + self.twice[2 * node + 1],
+            )
+        };
+
+        self.once[node] = if self.cover[node] > 0 { height } else { once };
+        self.twice[node] = match self.cover[node] {
+            0 => twice,
+            1 => once,
+            _ => height,
+        };
+    }
 }
 
-struct Cloth {
+pub struct Cloth {
     stride: usize,
-    squares: Box<[u8]>,
+    height: usize,
+    squares: Squares,
+}
+
+/// The number of claims on each square. Counts start out as bytes and are
+/// widened the first time a square is covered by more than 255 claims.
+enum Squares {
+    Narrow(Box<[u8]>),
+    Wide(Box<[u32]>),
 }
 
 impl Cloth {
-    fn new(width: usize, height: usize) -> Cloth {
+    pub fn new(width: usize, height: usize) -> Cloth {
         Cloth {
             stride: width,
-            squares: vec![0; width * height].into_boxed_slice(),
+            height,
+            squares: Squares::Narrow(vec![0; width * height].into_boxed_slice()),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.stride
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// A cloth just big enough for `claims`, with all of them rastered.
+    pub fn covering(claims: &[Claim]) -> Cloth {
+        let (width, height) = extent(claims);
+        let mut cloth = Cloth::new(width as usize, height as usize);
+
+        for claim in claims {
+            cloth.raster(claim);
         }
+
+        cloth
     }
 
-    fn inc(&mut self, x: usize, y: usize) {
-        self[(x, y)] = self[(x, y)].saturating_add(1);
+    /// The exact number of claims covering the square at `(x, y)`, which is 0
+    /// for squares outside the cloth.
+    pub fn count(&self, x: usize, y: usize) -> u32 {
+        if x >= self.stride || y >= self.height {
+            return 0;
+        }
+
+        let idx = x + y * self.stride;
+        match &self.squares {
+            Squares::Narrow(squares) => u32::from(squares[idx]),
+            Squares::Wide(squares) => squares[idx],
+        }
     }
 
-    fn contested(&self) -> u32 {
-        self.squares.iter().filter(|x| **x > 1).count() as u32
+    /// The largest number of claims covering any one square.
+    pub fn most(&self) -> u32 {
+        match &self.squares {
+            Squares::Narrow(squares) => squares.iter().cloned().max().map_or(0, u32::from),
+            Squares::Wide(squares) => squares.iter().cloned().max().unwrap_or(0),
+        }
     }
 
-    fn raster(&mut self, claim: &Claim) {
-        for i in claim.left..(claim.left + claim.width) {
-            for j in claim.top..(claim.top + claim.height) {
+    pub fn inc(&mut self, x: usize, y: usize) {
+        let idx = x + y * self.stride;
+
+        if let Squares::Narrow(squares) = &mut self.squares {
+            match squares[idx].checked_add(1) {
+                Some(count) => {
+                    squares[idx] = count;
+                    return;
+                }
+                None => {
+                    let wide = squares.iter().map(|&c| u32::from(c)).collect();
+                    self.squares = Squares::Wide(wide);
+                }
+            }
+        }
+
+        if let Squares::Wide(squares) = &mut self.squares {
+            squares[idx] += 1;
+        }
+    }
+
+    pub fn contested(&self) -> u64 {
+        match &self.squares {
+            Squares::Narrow(squares) => squares.iter().filter(|x| **x > 1).count() as u64,
+            Squares::Wide(squares) => squares.iter().filter(|x| **x > 1).count() as u64,
+        }
+    }
+
+    pub fn raster(&mut self, claim: &Claim) {
+        if claim.area() == 0 {
+            return;
+        }
+
+        for i in u64::from(claim.left)..claim.right() {
+            for j in u64::from(claim.top)..claim.bottom() {
                 self.inc(i as usize, j as usize);
             }
         }
     }
 
-    fn check(&self, claim: &Claim) -> bool {
-        for i in claim.left..(claim.left + claim.width) {
-            for j in claim.top..(claim.top + claim.height) {
-                if self[(i as usize, j as usize)] != 1 {
+    /// Whether `claim` is the only claim on each of its squares.
+    pub fn check(&self, claim: &Claim) -> bool {
+        if claim.area() == 0 {
+            return true;
+        }
+
+        for i in u64::from(claim.left)..claim.right() {
+            for j in u64::from(claim.top)..claim.bottom() {
+                if self.count(i as usize, j as usize) != 1 {
                     return false;
                 }
             }
@@ -86,49 +417,286 @@ impl Cloth {
     }
 }
 
-impl Index<(usize, usize)> for Cloth {
-    type Output = u8;
+/// The ids of the claims covering any part of the rectangle with its top left
+/// corner at `(left, top)`. A 1x1 rectangle gives the claims on one square.
+pub fn claims_covering(claims: &[Claim], left: u32, top: u32, width: u32, height: u32) -> Vec<u32> {
+    let area = Claim {
+        id: 0,
+        left,
+        top,
+        width,
+        height,
+    };
 
-    fn index(&self, idx: (usize, usize)) -> &u8 {
-        &self.squares[idx.0 + idx.1 * self.stride]
-    }
+    claims
+        .iter()
+        .filter(|c| c.overlap(&area) > 0)
+        .map(|c| c.id)
+        .collect()
 }
 
-impl IndexMut<(usize, usize)> for Cloth {
-    fn index_mut(&mut self, idx: (usize, usize)) -> &mut u8 {
-        &mut self.squares[idx.0 + idx.1 * self.stride]
+const EMPTY: Rgb = [24, 24, 24];
+const CLAIMED: Rgb = [40, 90, 160];
+const CONTESTED: Rgb = [255, 200, 0];
+const MOST_CONTESTED: Rgb = [200, 0, 0];
+const OUTLINE: Rgb = [0, 255, 0];
+
+/// Draw the fabric with each square coloured by the number of claims on it,
+/// shading from yellow to red as the count rises, and with the uncontested
+/// claims outlined in green.
+pub fn heatmap(claims: &[Claim]) -> Image {
+    let cloth = Cloth::covering(claims);
+    let mut image = Image::new(cloth.width(), cloth.height(), EMPTY);
+
+    let most = cloth.most();
+
+    for y in 0..cloth.height() {
+        for x in 0..cloth.width() {
+            let colour = match cloth.count(x, y) {
+                0 => EMPTY,
+                1 => CLAIMED,
+                count => {
+                    let t = f64::from(count - 2) / f64::from(most.max(3) - 2);
+                    let mut colour = CONTESTED;
+                    for (c, (&from, &to)) in
+                        colour.iter_mut().zip(CONTESTED.iter().zip(&MOST_CONTESTED))
+                    {
+                        *c =
+                            (f64::from(from) + (f64::from(to) - f64::from(from)) * t).round() as u8;
+                    }
+                    colour
+                }
+            };
+            image.set(x, y, colour);
+        }
     }
+
+    for claim in claims.iter().filter(|c| c.area() > 0 && cloth.check(c)) {
+        let (left, top) = (claim.left as usize, claim.top as usize);
+        let (right, bottom) = (claim.right() as usize - 1, claim.bottom() as usize - 1);
+
+        for x in left..=right {
+            image.set(x, top, OUTLINE);
+            image.set(x, bottom, OUTLINE);
+        }
+        for y in top..=bottom {
+            image.set(left, y, OUTLINE);
+            image.set(right, y, OUTLINE);
+        }
+    }
+
+    image
 }
 
-struct Claim {
-    id: u32,
-    left: u32,
-    top: u32,
-    width: u32,
-    height: u32,
+pub struct Claim {
+    pub id: u32,
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Claim {
+    pub fn right(&self) -> u64 {
+        u64::from(self.left) + u64::from(self.width)
+    }
+
+    pub fn bottom(&self) -> u64 {
+        u64::from(self.top) + u64::from(self.height)
+    }
+
+    pub fn area(&self) -> u64 {
+        u64::from(self.width) * u64::from(self.height)
+    }
+
+    /// The number of square inches within both claims.
+    pub fn overlap(&self, other: &Claim) -> u64 {
+        let span = |start1: u32, end1: u64, start2: u32, end2: u64| {
+            end1.min(end2).saturating_sub(u64::from(start1.max(start2)))
+        };
+
+        span(self.left, self.right(), other.left, other.right())
+            * span(self.top, self.bottom(), other.top, other.bottom())
+    }
 }
 
+static CLAIM: Pattern = Pattern::new(&[
+    ("#", "`#`"),
+    (r"(\d+)", "a claim id"),
+    (" @ ", "` @ `"),
+    (r"(\d+)", "the left edge"),
+    (",", "`,`"),
+    (r"(\d+)", "the top edge"),
+    (": ", "`: `"),
+    (r"(\d+)", "the width"),
+    ("x", "`x`"),
+    (r"(\d+)", "the height"),
+]);
+
 impl FromStr for Claim {
-    type Err = Error;
+    type Err = FieldError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let re = Regex::new(r"#(\d+) @ (\d+),(\d+): (\d+)x(\d+)").unwrap();
-        let caps = re.captures(s).ok_or_else(|| err_msg("Invalid format"))?;
-
-        let get = |idx| -> Result<u32, Self::Err> {
-            Ok(caps
-                .get(idx)
-                .ok_or_else(|| err_msg("Invalid format"))?
-                .as_str()
-                .parse()?)
-        };
+        let fields = CLAIM.captures(s)?;
 
         Ok(Claim {
-            id: get(1)?,
-            left: get(2)?,
-            top: get(3)?,
-            width: get(4)?,
-            height: get(5)?,
+            id: fields.get(1)?,
+            left: fields.get(2)?,
+            top: fields.get(3)?,
+            width: fields.get(4)?,
+            height: fields.get(5)?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "#1 @ 1,3: 4x4\n#2 @ 3,1: 4x4\n#3 @ 5,5: 2x2\n";
+
+    #[test]
+    fn parse_claim() {
+        let claim: Claim = "#123 @ 3,2: 5x4".parse().unwrap();
+        assert_eq!(
+            (claim.id, claim.left, claim.top, claim.width, claim.height),
+            (123, 3, 2, 5, 4)
+        );
+    }
+
+    #[test]
+    fn parse_error_column() {
+        let err = "#1 @ 3,x: 5x4".parse::<Claim>().err().unwrap();
+        assert_eq!(err, FieldError::new(8, "the top edge"));
+    }
+
+    #[test]
+    fn examples() {
+        let day = Day3::parse(EXAMPLE).unwrap();
+        assert_eq!(day.part1().unwrap(), 4);
+        assert_eq!(day.part2().unwrap(), 3);
+    }
+
+    #[test]
+    fn engines_agree() {
+        for input in &[EXAMPLE, INPUT] {
+            let claims = Day3::parse(input).unwrap().lines;
+            assert_eq!(Engine::choose(&claims), Engine::Dense);
+
+            assert_eq!(
+                contested(&claims, Engine::Sweep),
+                contested(&claims, Engine::Dense)
+            );
+
+            let ids = |engine| -> Vec<u32> {
+                uncontested(&claims, engine).iter().map(|c| c.id).collect()
+            };
+            assert_eq!(ids(Engine::Sweep), ids(Engine::Dense));
+        }
+    }
+
+    #[test]
+    fn overlap_report() {
+        let day = Day3::parse(EXAMPLE).unwrap();
+        let report = OverlapReport::new(&day.lines);
+
+        let overlaps: Vec<(u32, Vec<(u32, u64)>)> = report
+            .claims
+            .iter()
+            .map(|c| (c.id, c.overlaps.iter().map(|o| (o.id, o.area)).collect()))
+            .collect();
+        assert_eq!(
+            overlaps,
+            vec![(1, vec![(2, 4)]), (2, vec![(1, 4)]), (3, vec![])]
+        );
+        assert_eq!(report.uncontested, vec![3]);
+
+        let day = Day3::parse("#1 @ 0,0: 2x2\n#2 @ 1,1: 2x2\n").unwrap();
+        assert!(OverlapReport::new(&day.lines).uncontested.is_empty());
+        assert!(day.part2().is_err());
+    }
+
+    #[test]
+    fn exact_counts() {
+        let mut input = String::new();
+        for id in 1..=300 {
+            input.push_str(&format!("#{} @ 1,1: 2x1\n", id));
+        }
+        input.push_str("#301 @ 0,0: 2x2\n");
+        let day = Day3::parse(&input).unwrap();
+
+        let cloth = Cloth::covering(&day.lines);
+        assert_eq!(cloth.count(0, 0), 1);
+        assert_eq!(cloth.count(1, 1), 301);
+        assert_eq!(cloth.count(2, 1), 300);
+        assert_eq!(cloth.count(3, 0), 0);
+        assert_eq!(cloth.count(0, 2), 0);
+        assert_eq!(cloth.count(usize::MAX, usize::MAX), 0);
+        assert_eq!(cloth.most(), 301);
+        assert_eq!(cloth.contested(), 2);
+
+        assert_eq!(claims_covering(&day.lines, 2, 1, 1, 1).len(), 300);
+        assert_eq!(claims_covering(&day.lines, 0, 0, 1, 2), vec![301]);
+        assert_eq!(claims_covering(&day.lines, 0, 0, 10, 10).len(), 301);
+        assert!(claims_covering(&day.lines, 3, 0, 5, 5).is_empty());
+    }
+
+    #[test]
+    fn heatmap_colours() {
+        let day = Day3::parse(EXAMPLE).unwrap();
+        let image = heatmap(&day.lines);
+
+        assert_eq!((image.width(), image.height()), (7, 7));
+        assert_eq!(image.get(0, 0), EMPTY);
+        assert_eq!(image.get(1, 3), CLAIMED);
+        assert_eq!(image.get(3, 3), CONTESTED);
+        for &(x, y) in &[(5, 5), (6, 5), (5, 6), (6, 6)] {
+            assert_eq!(image.get(x, y), OUTLINE);
+        }
+    }
+
+    #[test]
+    fn huge_coordinates() {
+        let input = "#1 @ 1000000000,3000000000: 400000x400000\n\
+                     #2 @ 1000200000,3000100000: 400000x10\n\
+                     #3 @ 5,5: 2x2\n\
+                     #4 @ 4000000000,0: 1x1\n";
+        let day = Day3::parse(input).unwrap();
+
+        assert_eq!(Engine::choose(&day.lines), Engine::Sweep);
+        assert_eq!(day.part1().unwrap(), 2_000_000);
+        let ids: Vec<u32> = uncontested(&day.lines, Engine::Sweep)
+            .iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(ids, vec![3, 4]);
+    }
+
+    #[test]
+    fn empty_claims_far_away() {
+        let input = "#1 @ 4000000000,0: 1000000000x0\n\
+                     #2 @ 0,4000000000: 0x1000000000\n\
+                     #3 @ 1,1: 2x2\n\
+                     #4 @ 2,2: 2x2\n";
+        let day = Day3::parse(input).unwrap();
+
+        assert_eq!(Engine::choose(&day.lines), Engine::Dense);
+        assert_eq!(day.part1().unwrap(), 1);
+        assert_eq!(day.part2().unwrap(), 1);
+        assert_eq!(contested(&day.lines, Engine::Sweep), 1);
+
+        let wide = Day3::parse("#1 @ 0,0: 4000000000x0\n#2 @ 0,0: 1x1\n").unwrap();
+        assert_eq!(Engine::choose(&wide.lines), Engine::Dense);
+        assert_eq!(wide.part1().unwrap(), 0);
+
+        let strip = Day3::parse("#1 @ 0,0: 4000000000x1\n").unwrap();
+        assert_eq!(Engine::choose(&strip.lines), Engine::Sweep);
+    }
+
+    #[test]
+    fn real_input() {
+        let day = Day3::parse(INPUT).unwrap();
+        assert_eq!(day.part1().unwrap(), 118322);
+        assert_eq!(day.part2().unwrap(), 1178);
+    }
+}
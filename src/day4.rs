@@ -1,138 +1,929 @@
 // Day 4: Repose Record
 
 use std::collections::HashMap;
+use std::fmt::{self, Display};
 use std::str::FromStr;
 
-use chrono::{DateTime, TimeZone, Timelike, Utc};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc};
 use failure::{err_msg, Error};
-use regex::Regex;
+use serde_derive::Serialize;
 
-pub fn solve() {
-    let mut lines: Vec<Record> = include_str!("data/day4.txt")
-        .lines()
-        .map(|s| s.parse().unwrap())
-        .collect();
+use crate::parse::{parse_lines, Diagnostic, FieldError, ParseErrors, Pattern};
+use crate::solution::Solution;
 
-    lines.sort_by_key(|record| record.datetime);
-    let guards = parse_records(&lines);
+pub const INPUT: &str = include_str!("data/day4.txt");
 
-    println!("Part 1: {}", part1(&guards));
-    println!("Part 2: {}", part2(&guards));
+pub struct Day4 {
+    pub guards: HashMap<u32, Guard>,
 }
 
-fn part1(guards: &HashMap<u32, Guard>) -> u32 {
-    let (id, guard) = guards.iter().max_by_key(|(_, g)| g.time_asleep()).unwrap();
-    let (minute, _) = guard.sleepyest_minute();
-    id * minute
+impl Solution for Day4 {
+    type Part1 = u32;
+    type Part2 = u32;
+
+    fn parse(input: &str) -> Result<Self, Error> {
+        let records = parse_log(input)?;
+        let (guards, _) = parse_records(&records, Mode::Strict)?;
+
+        Ok(Day4 { guards })
+    }
+
+    fn part1(&self) -> Result<u32, Error> {
+        part1(&self.guards).ok_or_else(|| err_msg("No guard began a shift"))
+    }
+
+    fn part2(&self) -> Result<u32, Error> {
+        part2(&self.guards).ok_or_else(|| err_msg("No guard began a shift"))
+    }
 }
 
-fn part2(guards: &HashMap<u32, Guard>) -> u32 {
-    let (id, (minute, _)) = guards
-        .iter()
-        .map(|(id, g)| (id, g.sleepyest_minute()))
-        .max_by_key(|(_, (_, s))| *s)
-        .unwrap();
+pub fn part1(guards: &HashMap<u32, Guard>) -> Option<u32> {
+    strategy1(guards).first().map(|pick| pick.answer())
+}
+
+pub fn part2(guards: &HashMap<u32, Guard>) -> Option<u32> {
+    strategy2(guards).first().map(|pick| pick.answer())
+}
+
+/// A guard and minute picked by one of the puzzle's strategies.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Pick {
+    pub guard: u32,
+    pub minute: u32,
+}
+
+impl Pick {
+    pub fn answer(self) -> u32 {
+        self.guard * self.minute
+    }
+}
+
+/// Strategy 1: the guard who slept the most, and their sleepiest minute.
+/// Returns every tied pick in order of guard id then minute, so the first is
+/// the lowest id and earliest minute.
+pub fn strategy1(guards: &HashMap<u32, Guard>) -> Vec<Pick> {
+    picks(guards, choose_all(guards, Guard::time_asleep))
+}
+
+/// Strategy 2: the guard most often asleep on the same minute, and that
+/// minute. Ties are returned as for `strategy1`.
+pub fn strategy2(guards: &HashMap<u32, Guard>) -> Vec<Pick> {
+    picks(guards, choose_all(guards, |g| g.sleepyest_minute().1))
+}
+
+fn picks<K>(guards: &HashMap<u32, Guard>, chosen: Option<(Vec<u32>, K)>) -> Vec<Pick> {
+    let ids = chosen.map_or_else(Vec::new, |(ids, _)| ids);
+    let mut picks = Vec::new();
+
+    for guard in ids {
+        let (minutes, _) = guards[&guard].sleepiest_all(Bucket::MidnightMinute);
+        picks.extend(minutes.into_iter().map(|minute| Pick {
+            guard,
+            minute: minute as u32,
+        }));
+    }
 
-    id * minute
+    picks
 }
 
-fn parse_records(records: &[Record]) -> HashMap<u32, Guard> {
-    let mut guards = HashMap::new();
-    let mut guard = &mut Guard::new();
-    let mut start = 0;
+/// How `parse_records` deals with a log that doesn't make sense.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// Fail, reporting every anomaly in the log.
+    Strict,
+    /// Repair each anomaly as well as possible and carry on.
+    Lenient,
+}
+
+/// Something wrong with the sequence of events in a log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anomaly {
+    /// 1-based line of the record at fault.
+    pub line: usize,
+    pub datetime: DateTime<Utc>,
+    pub text: String,
+    pub kind: AnomalyKind,
+}
 
-    for record in records {
-        match record.event {
-            Event::BeginShift(id) => {
-                guard = guards.entry(id).or_insert_with(Guard::new);
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnomalyKind {
+    /// Someone fell asleep or woke up before any guard began a shift. The
+    /// record is ignored.
+    NoGuard,
+    /// The guard woke up without having fallen asleep. The record is ignored.
+    NotAsleep,
+    /// The guard fell asleep while already asleep. The record is ignored.
+    AlreadyAsleep,
+    /// The guard was still asleep at the end of their shift. The nap is
+    /// closed when the shift ends.
+    StillAsleep(u32),
+}
+
+impl Anomaly {
+    fn new(line: usize, record: &Record, kind: AnomalyKind) -> Anomaly {
+        Anomaly {
+            line,
+            datetime: record.datetime,
+            text: record.to_string(),
+            kind,
+        }
+    }
+
+    /// What should have happened instead.
+    pub fn expected(&self) -> String {
+        match self.kind {
+            AnomalyKind::NoGuard => "a guard to begin their shift first".to_owned(),
+            AnomalyKind::NotAsleep => "the guard to fall asleep before waking up".to_owned(),
+            AnomalyKind::AlreadyAsleep => {
+                "the guard to wake up before falling asleep again".to_owned()
             }
-            Event::FallAsleep => {
-                start = record.datetime.minute();
+            AnomalyKind::StillAsleep(id) => {
+                format!("guard #{} to wake up before the end of their shift", id)
             }
-            Event::WakeUp => {
-                guard.naps.push(Nap::new(start, record.datetime.minute()));
+        }
+    }
+}
+
+/// Every anomaly in a log, as parse errors pointing at the records at fault.
+pub fn anomaly_errors(anomalies: &[Anomaly]) -> ParseErrors {
+    ParseErrors::new(
+        anomalies
+            .iter()
+            .map(|a| Diagnostic {
+                line: a.line,
+                column: 1,
+                text: a.text.clone(),
+                expected: a.expected(),
+            })
+            .collect(),
+    )
+}
+
+/// Parse a log, numbering its records by line and sorting them into order.
+pub fn parse_log(input: &str) -> Result<Vec<(usize, Record)>, ParseErrors> {
+    let mut records: Vec<(usize, Record)> = parse_lines::<Record>(input)?
+        .into_iter()
+        .enumerate()
+        .map(|(idx, record)| (idx + 1, record))
+        .collect();
+
+    records.sort_by_key(|(_, record)| record.datetime);
+    Ok(records)
+}
+
+/// Work out each guard's naps from a sorted log, along with any anomalies
+/// that were repaired. In strict mode any anomaly is an error instead.
+pub fn parse_records(
+    records: &[(usize, Record)],
+    mode: Mode,
+) -> Result<(HashMap<u32, Guard>, Vec<Anomaly>), ParseErrors> {
+    let schedule = Schedule::new(records, mode)?;
+    Ok((schedule.guards, schedule.anomalies))
+}
+
+/// Every shift in a log, for answering questions about who was on duty and
+/// who was asleep when.
+pub struct Schedule {
+    pub shifts: Vec<Shift>,
+    /// Every guard, with their naps from all of their shifts.
+    pub guards: HashMap<u32, Guard>,
+    /// The anomalies that were repaired in lenient mode.
+    pub anomalies: Vec<Anomaly>,
+}
+
+impl Schedule {
+    /// Build the schedule from a sorted log. In strict mode any anomaly in
+    /// the log is an error.
+    pub fn new(records: &[(usize, Record)], mode: Mode) -> Result<Schedule, ParseErrors> {
+        let mut shifts: Vec<Shift> = Vec::new();
+        let mut anomalies = Vec::new();
+        let mut asleep: Option<(usize, &Record)> = None;
+
+        for (line, record) in records {
+            match (&record.event, shifts.last_mut(), asleep) {
+                (&Event::BeginShift(id), shift, _) => {
+                    if let Some(shift) = shift {
+                        shift.end = shift.end.min(record.datetime);
+                        end_shift(shift, &mut anomalies, asleep.take());
+                    }
+                    shifts.push(Shift::new(id, record.datetime));
+                }
+                (_, None, _) => {
+                    anomalies.push(Anomaly::new(*line, record, AnomalyKind::NoGuard));
+                }
+                (Event::FallAsleep, Some(_), None) => {
+                    asleep = Some((*line, record));
+                }
+                (Event::FallAsleep, Some(_), Some(_)) => {
+                    anomalies.push(Anomaly::new(*line, record, AnomalyKind::AlreadyAsleep));
+                }
+                (Event::WakeUp, Some(_), None) => {
+                    anomalies.push(Anomaly::new(*line, record, AnomalyKind::NotAsleep));
+                }
+                (Event::WakeUp, Some(shift), Some((_, start))) => {
+                    shift.naps.push(Nap::new(start.datetime, record.datetime));
+                    asleep = None;
+                }
+            }
+
+            if let Some(shift) = shifts.last_mut() {
+                shift.end = shift.end.max(record.datetime);
             }
         }
+
+        if let Some(shift) = shifts.last_mut() {
+            end_shift(shift, &mut anomalies, asleep);
+        }
+
+        if mode == Mode::Strict && !anomalies.is_empty() {
+            anomalies.sort_by_key(|a| a.line);
+            return Err(anomaly_errors(&anomalies));
+        }
+
+        let mut guards: HashMap<u32, Guard> = HashMap::new();
+        for shift in &shifts {
+            let guard = guards.entry(shift.guard).or_default();
+            guard.shifts += 1;
+            guard.naps.extend(shift.naps.iter().cloned());
+        }
+
+        Ok(Schedule {
+            shifts,
+            guards,
+            anomalies,
+        })
+    }
+
+    /// The shift in progress at `time`, or `None` between shifts.
+    pub fn shift_at(&self, time: DateTime<Utc>) -> Option<&Shift> {
+        let next = self.shifts.partition_point(|s| s.start <= time);
+        next.checked_sub(1)
+            .map(|idx| &self.shifts[idx])
+            .filter(|s| time < s.end)
+    }
+
+    /// The guard on duty at `time`.
+    pub fn on_duty(&self, time: DateTime<Utc>) -> Option<u32> {
+        self.shift_at(time).map(|s| s.guard)
+    }
+
+    /// The guard asleep at `time`, if the guard on duty was asleep.
+    pub fn asleep(&self, time: DateTime<Utc>) -> Option<u32> {
+        self.shift_at(time)
+            .filter(|s| s.asleep_at(time))
+            .map(|s| s.guard)
     }
 
-    guards
+    /// The guards on duty at any time from `from` up to `to`, in order of id.
+    pub fn on_duty_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<u32> {
+        let mut guards: Vec<u32> = self
+            .shifts
+            .iter()
+            .filter(|s| s.start < to && s.end > from)
+            .map(|s| s.guard)
+            .collect();
+
+        guards.sort();
+        guards.dedup();
+        guards
+    }
+
+    /// The shifts covering the midnight hour of `date`.
+    pub fn day(&self, date: NaiveDate) -> Vec<&Shift> {
+        self.shifts.iter().filter(|s| s.date() == date).collect()
+    }
+
+    /// The guard that `strategy` rates highest, along with their rating.
+    pub fn choose<K: Ord>(&self, strategy: impl Fn(&Guard) -> K) -> Option<(u32, K)> {
+        choose(&self.guards, strategy)
+    }
+
+    /// Every guard tied for the highest rating from `strategy`.
+    pub fn choose_all<K: Ord>(&self, strategy: impl Fn(&Guard) -> K) -> Option<(Vec<u32>, K)> {
+        choose_all(&self.guards, strategy)
+    }
 }
 
-struct Guard {
-    naps: Vec<Nap>,
+/// The guard that `strategy` rates highest, along with their rating. Ties go
+/// to the guard with the lowest id.
+pub fn choose<K: Ord>(
+    guards: &HashMap<u32, Guard>,
+    strategy: impl Fn(&Guard) -> K,
+) -> Option<(u32, K)> {
+    choose_all(guards, strategy).map(|(ids, rating)| (ids[0], rating))
+}
+
+/// The ids of every guard tied for the highest rating from `strategy`, in
+/// order, along with that rating.
+pub fn choose_all<K: Ord>(
+    guards: &HashMap<u32, Guard>,
+    strategy: impl Fn(&Guard) -> K,
+) -> Option<(Vec<u32>, K)> {
+    let mut rated: Vec<(u32, K)> = guards
+        .iter()
+        .map(|(&id, guard)| (id, strategy(guard)))
+        .collect();
+    rated.sort_by(|(id1, a), (id2, b)| b.cmp(a).then(id1.cmp(id2)));
+
+    let mut rated = rated.into_iter();
+    let (first, best) = rated.next()?;
+
+    let mut ids = vec![first];
+    ids.extend(rated.take_while(|(_, r)| *r == best).map(|(id, _)| id));
+
+    Some((ids, best))
+}
+
+/// Close the nap of a guard who is still asleep as their shift ends.
+fn end_shift(shift: &mut Shift, anomalies: &mut Vec<Anomaly>, asleep: Option<(usize, &Record)>) {
+    if let Some((line, start)) = asleep {
+        let kind = AnomalyKind::StillAsleep(shift.guard);
+        anomalies.push(Anomaly::new(line, start, kind));
+        shift.naps.push(Nap::new(start.datetime, shift.end));
+    }
+}
+
+/// The start of `hour` on `date`.
+fn hour_of(date: NaiveDate, hour: u32) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&date.and_hms_opt(hour, 0, 0).unwrap())
+}
+
+/// The day whose midnight hour a shift beginning at `start` covers.
+fn shift_date(start: DateTime<Utc>) -> NaiveDate {
+    let date = start.date_naive();
+    if start.hour() >= 12 {
+        date.succ_opt().unwrap()
+    } else {
+        date
+    }
+}
+
+/// One guard's shift, lasting until the end of its midnight hour.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shift {
+    pub guard: u32,
+    pub start: DateTime<Utc>,
+    /// 01:00 on the shift's date, or its last record if that is later, but
+    /// no later than when the next shift began.
+    pub end: DateTime<Utc>,
+    pub naps: Vec<Nap>,
+}
+
+impl Shift {
+    /// A shift that ends with its midnight hour, until its records show
+    /// otherwise.
+    pub fn new(guard: u32, start: DateTime<Utc>) -> Shift {
+        Shift {
+            guard,
+            start,
+            end: hour_of(shift_date(start), 1).max(start),
+            naps: Vec::new(),
+        }
+    }
+
+    pub fn asleep_at(&self, time: DateTime<Utc>) -> bool {
+        self.naps.iter().any(|n| n.covers(time))
+    }
+
+    /// The day whose midnight hour the shift covers. Shifts that begin
+    /// before midnight belong to the next day.
+    pub fn date(&self) -> NaiveDate {
+        shift_date(self.start)
+    }
+
+    /// The midnight hour of the shift as drawn in the puzzle, with `#` for
+    /// each minute the guard was asleep and `.` for each minute awake.
+    pub fn chart(&self) -> String {
+        let midnight = hour_of(self.date(), 0);
+        (0..60)
+            .map(|m| {
+                if self.asleep_at(midnight + Duration::minutes(m)) {
+                    '#'
+                } else {
+                    '.'
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Default)]
+pub struct Guard {
+    pub naps: Vec<Nap>,
+    pub shifts: u32,
 }
 
 impl Guard {
-    fn new() -> Guard {
-        Guard { naps: Vec::new() }
+    pub fn new() -> Guard {
+        Guard::default()
     }
 
-    fn time_asleep(&self) -> u32 {
-        self.naps.iter().map(|n| n.end - n.start).sum()
+    pub fn time_asleep(&self) -> u32 {
+        self.naps.iter().map(Nap::minutes).sum()
     }
 
-    fn sleepyest_minute(&self) -> (u32, u32) {
-        let mut mins = [0u32; 60];
+    /// How many minutes the guard spent asleep in each bucket. With minute
+    /// buckets, that's how many times they were asleep during each minute.
+    pub fn histogram(&self, bucket: Bucket) -> Vec<u32> {
+        let mut counts = vec![0u32; bucket.count()];
 
         for nap in &self.naps {
-            for min in nap.start..nap.end {
-                mins[min as usize] += 1;
+            for minute in nap.each_minute() {
+                if let Some(idx) = bucket.index(minute) {
+                    counts[idx] += 1;
+                }
             }
         }
 
-        let (minute, &duration) = mins.iter().enumerate().max_by_key(|&(_, dur)| dur).unwrap();
-        (minute as u32, duration)
+        counts
+    }
+
+    /// The bucket the guard spent the most time asleep in, and how long.
+    /// Ties go to the earliest bucket.
+    pub fn sleepiest(&self, bucket: Bucket) -> (usize, u32) {
+        let (buckets, count) = self.sleepiest_all(bucket);
+        (buckets[0], count)
+    }
+
+    /// Every bucket tied for the most time asleep, in order, and how long.
+    pub fn sleepiest_all(&self, bucket: Bucket) -> (Vec<usize>, u32) {
+        let counts = self.histogram(bucket);
+        let most = counts.iter().cloned().max().unwrap_or(0);
+
+        let buckets = (0..counts.len()).filter(|&idx| counts[idx] == most);
+        (buckets.collect(), most)
+    }
+
+    pub fn sleepyest_minute(&self) -> (u32, u32) {
+        let (minute, count) = self.sleepiest(Bucket::MidnightMinute);
+        (minute as u32, count)
     }
 }
 
-struct Nap {
-    start: u32,
-    end: u32,
+/// The periods of the day that time asleep is counted in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bucket {
+    /// Each minute of the midnight hour, as in the puzzle. Time asleep outside
+    /// the midnight hour isn't counted.
+    MidnightMinute,
+    /// Each minute of the day.
+    DayMinute,
+    /// Each hour of the day.
+    Hour,
+}
+
+impl Bucket {
+    pub fn count(self) -> usize {
+        match self {
+            Bucket::MidnightMinute => 60,
+            Bucket::DayMinute => 24 * 60,
+            Bucket::Hour => 24,
+        }
+    }
+
+    /// The bucket containing the minute that starts at `time`.
+    pub fn index(self, time: DateTime<Utc>) -> Option<usize> {
+        let (hour, minute) = (time.hour() as usize, time.minute() as usize);
+
+        match self {
+            Bucket::MidnightMinute if hour == 0 => Some(minute),
+            Bucket::MidnightMinute => None,
+            Bucket::DayMinute => Some(hour * 60 + minute),
+            Bucket::Hour => Some(hour),
+        }
+    }
+
+    /// The time of day that bucket `idx` starts, like `00:24`.
+    pub fn start(self, idx: usize) -> String {
+        let minutes = match self {
+            Bucket::MidnightMinute | Bucket::DayMinute => idx,
+            Bucket::Hour => idx * 60,
+        };
+        format!("{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+
+    /// A short label for bucket `idx`. Labels of the same bucket size all have
+    /// the same number of digits.
+    pub fn label(self, idx: usize) -> String {
+        match self {
+            Bucket::MidnightMinute | Bucket::Hour => format!("{:02}", idx),
+            Bucket::DayMinute => format!("{:02}{:02}", idx / 60, idx % 60),
+        }
+    }
+}
+
+/// What one guard got up to over all of their shifts.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GuardSummary {
+    pub id: u32,
+    pub minutes_asleep: u32,
+    pub shifts: u32,
+    /// The index of the bucket the guard spent the most time asleep in.
+    pub sleepiest: usize,
+    /// The minutes the guard spent asleep in the `sleepiest` bucket.
+    pub times: u32,
+    pub histogram: Vec<u32>,
+}
+
+impl GuardSummary {
+    pub fn new(id: u32, guard: &Guard, bucket: Bucket) -> GuardSummary {
+        let (sleepiest, times) = guard.sleepiest(bucket);
+
+        GuardSummary {
+            id,
+            minutes_asleep: guard.time_asleep(),
+            shifts: guard.shifts,
+            sleepiest,
+            times,
+            histogram: guard.histogram(bucket),
+        }
+    }
+}
+
+/// A summary of every guard, in order of their ids.
+pub fn summaries(guards: &HashMap<u32, Guard>, bucket: Bucket) -> Vec<GuardSummary> {
+    let mut summaries: Vec<GuardSummary> = guards
+        .iter()
+        .map(|(&id, guard)| GuardSummary::new(id, guard, bucket))
+        .collect();
+
+    summaries.sort_by_key(|s| s.id);
+    summaries
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Nap {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
 }
 
 impl Nap {
-    fn new(start: u32, end: u32) -> Nap {
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> Nap {
         Nap { start, end }
     }
+
+    pub fn covers(&self, time: DateTime<Utc>) -> bool {
+        self.start <= time && time < self.end
+    }
+
+    pub fn minutes(&self) -> u32 {
+        (self.end - self.start).num_minutes().max(0) as u32
+    }
+
+    /// The start of each minute of the nap.
+    pub fn each_minute(&self) -> impl Iterator<Item = DateTime<Utc>> {
+        let start = self.start;
+        (0..self.minutes()).map(move |m| start + Duration::minutes(m.into()))
+    }
 }
 
-enum Event {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
     BeginShift(u32),
     FallAsleep,
     WakeUp,
 }
 
-struct Record {
-    datetime: DateTime<Utc>,
-    event: Event,
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub datetime: DateTime<Utc>,
+    pub event: Event,
+}
+
+impl Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}] ", self.datetime.format("%Y-%m-%d %H:%M"))?;
+        match self.event {
+            Event::BeginShift(id) => write!(f, "Guard #{} begins shift", id),
+            Event::FallAsleep => write!(f, "falls asleep"),
+            Event::WakeUp => write!(f, "wakes up"),
+        }
+    }
 }
 
+static RECORD: Pattern = Pattern::new(&[
+    (r"\[", "`[`"),
+    (
+        r"(\d{4}-\d{2}-\d{2} \d{2}:\d{2})",
+        "a timestamp like `1518-11-01 00:00`",
+    ),
+    (r"\] ", "`] `"),
+    (
+        r"(?:Guard #(\d+) begins shift|(falls asleep)|(wakes up))",
+        "`Guard #<id> begins shift`, `falls asleep` or `wakes up`",
+    ),
+]);
+
 impl FromStr for Record {
-    type Err = Error;
+    type Err = FieldError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let re = Regex::new(r"\[(.*?)\] (?:Guard #(\d+) begins shift|(falls asleep)|(wakes up))")
-            .unwrap();
-        let caps = re.captures(s).ok_or_else(|| err_msg("Invalid format"))?;
+        let fields = RECORD.captures(s)?;
 
-        let datetime_str = caps
-            .get(1)
-            .ok_or_else(|| err_msg("Invalid format"))?
-            .as_str();
-        let datetime = Utc.datetime_from_str(datetime_str, "%Y-%m-%d %H:%M")?;
+        let datetime = NaiveDateTime::parse_from_str(fields.str(1)?, "%Y-%m-%d %H:%M")
+            .map_err(|e| fields.error(1, format!("a valid timestamp ({})", e)))?;
+        let datetime = Utc.from_utc_datetime(&datetime);
 
         let event = {
-            if let Some(group) = caps.get(2) {
-                Event::BeginShift(group.as_str().parse()?)
-            } else if caps.get(3).is_some() {
+            if fields.has(2) {
+                Event::BeginShift(fields.get(2)?)
+            } else if fields.has(3) {
                 Event::FallAsleep
-            } else if caps.get(4).is_some() {
-                Event::WakeUp
             } else {
-                return Err(err_msg("Invalid format"));
+                Event::WakeUp
             }
         };
 
         Ok(Record { datetime, event })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:05] falls asleep
+[1518-11-01 00:25] wakes up
+[1518-11-01 00:30] falls asleep
+[1518-11-01 00:55] wakes up
+[1518-11-01 23:58] Guard #99 begins shift
+[1518-11-02 00:40] falls asleep
+[1518-11-02 00:50] wakes up
+[1518-11-03 00:05] Guard #10 begins shift
+[1518-11-03 00:24] falls asleep
+[1518-11-03 00:29] wakes up
+[1518-11-04 00:02] Guard #99 begins shift
+[1518-11-04 00:36] falls asleep
+[1518-11-04 00:46] wakes up
+[1518-11-05 00:03] Guard #99 begins shift
+[1518-11-05 00:45] falls asleep
+[1518-11-05 00:55] wakes up
+";
+
+    #[test]
+    fn example_guards() {
+        let day = Day4::parse(EXAMPLE).unwrap();
+        let guard = &day.guards[&10];
+        assert_eq!(guard.time_asleep(), 50);
+        assert_eq!(guard.sleepyest_minute(), (24, 2));
+        assert_eq!(day.guards[&99].sleepyest_minute(), (45, 3));
+    }
+
+    #[test]
+    fn example_summaries() {
+        let day = Day4::parse(EXAMPLE).unwrap();
+        let summaries = summaries(&day.guards, Bucket::MidnightMinute);
+
+        let ids: Vec<u32> = summaries.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![10, 99]);
+
+        let guard = &summaries[0];
+        assert_eq!(
+            (
+                guard.minutes_asleep,
+                guard.shifts,
+                guard.sleepiest,
+                guard.times
+            ),
+            (50, 2, 24, 2)
+        );
+        assert_eq!(guard.histogram[..6], [0, 0, 0, 0, 0, 1]);
+        assert_eq!(guard.histogram.iter().sum::<u32>(), 50);
+
+        assert_eq!(summaries[1].shifts, 3);
+    }
+
+    #[test]
+    fn naps_across_hours_and_midnight() {
+        let day = Day4::parse(
+            "[1518-11-01 23:50] Guard #7 begins shift\n\
+             [1518-11-01 23:58] falls asleep\n\
+             [1518-11-02 00:05] wakes up\n\
+             [1518-11-03 00:00] Guard #7 begins shift\n\
+             [1518-11-03 00:30] falls asleep\n\
+             [1518-11-03 02:10] wakes up\n",
+        )
+        .unwrap();
+        let guard = &day.guards[&7];
+
+        assert_eq!(guard.time_asleep(), 7 + 100);
+
+        let midnight = guard.histogram(Bucket::MidnightMinute);
+        assert_eq!(midnight.iter().sum::<u32>(), 5 + 30);
+        assert_eq!(midnight[..6], [1, 1, 1, 1, 1, 0]);
+        assert_eq!(midnight[30], 1);
+
+        let minutes = guard.histogram(Bucket::DayMinute);
+        assert_eq!(minutes.len(), 1440);
+        assert_eq!((minutes[1437], minutes[1438], minutes[1439]), (0, 1, 1));
+        assert_eq!(minutes.iter().sum::<u32>(), 107);
+
+        let hours = guard.histogram(Bucket::Hour);
+        assert_eq!(hours[..3], [35, 60, 10]);
+        assert_eq!(hours[23], 2);
+        assert_eq!(guard.sleepiest(Bucket::Hour), (1, 60));
+
+        assert_eq!(Bucket::DayMinute.start(1438), "23:58");
+        assert_eq!(Bucket::Hour.label(7), "07");
+    }
+
+    #[test]
+    fn schedule_queries() {
+        let records = parse_log(EXAMPLE).unwrap();
+        let schedule = Schedule::new(&records, Mode::Strict).unwrap();
+        let at = |s: &str| {
+            Utc.from_utc_datetime(&NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap())
+        };
+
+        assert_eq!(schedule.shifts.len(), 5);
+        assert_eq!(schedule.on_duty(at("1518-10-31 23:59")), None);
+        assert_eq!(schedule.on_duty(at("1518-11-01 00:59")), Some(10));
+        assert_eq!(schedule.on_duty(at("1518-11-01 12:00")), None);
+        assert_eq!(schedule.on_duty(at("1518-11-01 23:59")), Some(99));
+        assert_eq!(schedule.asleep(at("1518-11-01 00:05")), Some(10));
+        assert_eq!(schedule.asleep(at("1518-11-01 00:25")), None);
+        assert_eq!(schedule.asleep(at("1518-11-05 00:54")), Some(99));
+        assert_eq!(schedule.asleep(at("1518-11-05 00:55")), None);
+        assert_eq!(schedule.on_duty(at("1518-11-05 00:59")), Some(99));
+        assert_eq!(schedule.on_duty(at("1518-11-05 01:00")), None);
+        assert_eq!(schedule.on_duty(at("1600-01-01 00:00")), None);
+        assert_eq!(schedule.asleep(at("1600-01-01 00:00")), None);
+
+        assert_eq!(
+            schedule.on_duty_between(at("1518-11-01 00:30"), at("1518-11-03 00:06")),
+            vec![10, 99]
+        );
+        assert!(schedule
+            .on_duty_between(at("1518-11-01 01:00"), at("1518-11-01 23:58"))
+            .is_empty());
+        assert_eq!(
+            schedule.on_duty_between(at("1518-11-04 00:02"), at("1518-11-06 00:00")),
+            vec![99]
+        );
+        assert!(schedule
+            .on_duty_between(at("1518-11-05 01:00"), at("1600-01-01 00:00"))
+            .is_empty());
+
+        let day = NaiveDate::from_ymd_opt(1518, 11, 2).unwrap();
+        let shifts = schedule.day(day);
+        assert_eq!(shifts.len(), 1);
+        assert_eq!(shifts[0].guard, 99);
+        assert_eq!(
+            shifts[0].chart(),
+            "........................................##########.........."
+        );
+
+        let longest_nap = schedule.choose(|g| g.naps.iter().map(Nap::minutes).max());
+        assert_eq!(longest_nap, Some((10, Some(25))));
+    }
+
+    #[test]
+    fn ties_are_broken_by_lowest_id_and_earliest_minute() {
+        let input = "\
+[1518-11-01 00:00] Guard #20 begins shift
+[1518-11-01 00:10] falls asleep
+[1518-11-01 00:20] wakes up
+[1518-11-01 00:30] falls asleep
+[1518-11-01 00:40] wakes up
+[1518-11-02 00:00] Guard #7 begins shift
+[1518-11-02 00:50] falls asleep
+[1518-11-02 00:55] wakes up
+[1518-11-03 00:00] Guard #7 begins shift
+[1518-11-03 00:05] falls asleep
+[1518-11-03 00:20] wakes up
+";
+        // Both guards sleep for 20 minutes, never twice on the same minute.
+        for _ in 0..10 {
+            let day = Day4::parse(input).unwrap();
+            assert_eq!(day.part1().unwrap(), 7 * 5);
+            assert_eq!(day.part2().unwrap(), 7 * 5);
+
+            let picks = strategy1(&day.guards);
+            assert_eq!(picks.len(), 40);
+            assert_eq!(
+                picks[0],
+                Pick {
+                    guard: 7,
+                    minute: 5
+                }
+            );
+            assert_eq!(
+                picks[15],
+                Pick {
+                    guard: 7,
+                    minute: 50
+                }
+            );
+            assert_eq!(
+                picks[20],
+                Pick {
+                    guard: 20,
+                    minute: 10
+                }
+            );
+            assert_eq!(strategy2(&day.guards), picks);
+
+            let (ids, rating) = choose_all(&day.guards, Guard::time_asleep).unwrap();
+            assert_eq!((ids, rating), (vec![7, 20], 20));
+        }
+
+        let guard = Guard::new();
+        assert_eq!(guard.sleepyest_minute(), (0, 0));
+        assert_eq!(guard.sleepiest_all(Bucket::Hour).0.len(), 24);
+    }
+
+    #[test]
+    fn examples() {
+        let day = Day4::parse(EXAMPLE).unwrap();
+        assert_eq!(day.part1().unwrap(), 240);
+        assert_eq!(day.part2().unwrap(), 4455);
+    }
+
+    #[test]
+    fn no_guards() {
+        let day = Day4::parse("").unwrap();
+        assert!(day.part1().is_err());
+        assert!(day.part2().is_err());
+    }
+
+    #[test]
+    fn records_are_sorted() {
+        let shuffled: String = EXAMPLE.lines().rev().map(|l| format!("{}\n", l)).collect();
+        let day = Day4::parse(&shuffled).unwrap();
+        assert_eq!(day.part1().unwrap(), 240);
+    }
+
+    const BROKEN: &str = "\
+[1518-11-01 00:05] falls asleep
+[1518-11-01 23:58] Guard #99 begins shift
+[1518-11-02 00:10] wakes up
+[1518-11-02 00:40] falls asleep
+[1518-11-02 00:45] falls asleep
+[1518-11-02 00:50] wakes up
+[1518-11-03 00:05] Guard #10 begins shift
+[1518-11-03 00:24] falls asleep
+[1518-11-04 00:02] Guard #99 begins shift
+[1518-11-04 00:36] falls asleep
+";
+
+    #[test]
+    fn strict_reports_anomalies() {
+        let errors = Day4::parse(BROKEN)
+            .err()
+            .unwrap()
+            .downcast::<ParseErrors>()
+            .unwrap();
+
+        let lines: Vec<(usize, &str)> = errors
+            .diagnostics
+            .iter()
+            .map(|d| (d.line, d.text.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (1, "[1518-11-01 00:05] falls asleep"),
+                (3, "[1518-11-02 00:10] wakes up"),
+                (5, "[1518-11-02 00:45] falls asleep"),
+                (8, "[1518-11-03 00:24] falls asleep"),
+                (10, "[1518-11-04 00:36] falls asleep"),
+            ]
+        );
+        assert_eq!(
+            errors.diagnostics[3].expected,
+            "guard #10 to wake up before the end of their shift"
+        );
+    }
+
+    #[test]
+    fn lenient_repairs_anomalies() {
+        let records = parse_log(BROKEN).unwrap();
+        let (guards, anomalies) = parse_records(&records, Mode::Lenient).unwrap();
+
+        let kinds: Vec<AnomalyKind> = anomalies.iter().map(|a| a.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                AnomalyKind::NoGuard,
+                AnomalyKind::NotAsleep,
+                AnomalyKind::AlreadyAsleep,
+                AnomalyKind::StillAsleep(10),
+                AnomalyKind::StillAsleep(99),
+            ]
+        );
+        assert_eq!(anomalies[0].datetime.minute(), 5);
+
+        assert_eq!(guards[&10].time_asleep(), 36);
+        assert_eq!(guards[&99].time_asleep(), 10 + 24);
+        assert_eq!(guards[&99].shifts, 2);
+    }
+
+    #[test]
+    fn real_input() {
+        let day = Day4::parse(INPUT).unwrap();
+        assert_eq!(day.part1().unwrap(), 106710);
+        assert_eq!(day.part2().unwrap(), 10491);
+    }
+}
@@ -1,50 +1,175 @@
 use std::collections::HashSet;
 
-pub fn solve() {
-    let polymer = include_str!("data/day5.txt").trim().as_bytes();
+use failure::Error;
 
-    println!("Part 1: {}", part1(&polymer[..]));
-    println!("Part 2: {}", part2(&polymer[..]));
-}
+use crate::parse::{column, FieldError, ParseErrors};
+use crate::solution::Solution;
+
+pub const INPUT: &str = include_str!("data/day5.txt");
 
-fn part1(polymer: &[u8]) -> usize {
-    react(polymer.to_owned()).len()
+pub struct Day5 {
+    /// The polymer after every reaction has run.
+    pub reacted: Vec<u8>,
 }
 
-fn part2(polymer: &[u8]) -> usize {
-    let units: HashSet<u8> = polymer.iter().map(u8::to_ascii_lowercase).collect();
+impl Solution for Day5 {
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: &str) -> Result<Self, Error> {
+        let mut polymer = Vec::new();
+        let mut errors = ParseErrors::new(Vec::new());
+
+        // Long polymers may be wrapped over several lines.
+        for (idx, line) in input.lines().enumerate() {
+            let units = line.trim_start();
+            let offset = line.len() - units.len();
+            let units = units.trim_end();
+
+            match units.find(|c: char| !c.is_ascii_alphabetic()) {
+                Some(bad) => errors.push(
+                    idx + 1,
+                    line,
+                    FieldError::new(
+                        column(line, offset + bad),
+                        "a unit letter from a to z or A to Z",
+                    ),
+                ),
+                None => polymer.extend_from_slice(units.as_bytes()),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors.into());
+        }
 
-    let mut lengths = Vec::new();
+        Ok(Day5 {
+            reacted: react(polymer),
+        })
+    }
 
-    for u in units {
-        let mut reduced_polymer = polymer.to_owned();
-        reduced_polymer.retain(|c| !c.eq_ignore_ascii_case(&u));
-        lengths.push(react(reduced_polymer).len());
+    fn part1(&self) -> Result<usize, Error> {
+        Ok(self.reacted.len())
     }
 
-    lengths.into_iter().min().unwrap()
+    fn part2(&self) -> Result<usize, Error> {
+        Ok(part2(&self.reacted))
+    }
 }
 
-fn react(polymer: Vec<u8>) -> Vec<u8> {
-    let mut polymer = polymer;
+pub fn part1(polymer: &[u8]) -> usize {
+    react(polymer.iter().cloned()).len()
+}
 
-    let mut i = 0;
+/// Removing a unit type can't stop any of the reactions in the original
+/// polymer, so `polymer` may already be fully reacted, which makes each
+/// removal much cheaper.
+pub fn part2(polymer: &[u8]) -> usize {
+    let units: HashSet<u8> = polymer.iter().map(u8::to_ascii_lowercase).collect();
 
-    while i + 1 < polymer.len() {
-        if units_react(polymer[i], polymer[i + 1]) {
-            polymer.remove(i);
-            polymer.remove(i);
-            if i > 0 {
-                i -= 1;
+    units
+        .into_iter()
+        .map(|u| {
+            let reduced = polymer
+                .iter()
+                .cloned()
+                .filter(|c| !c.eq_ignore_ascii_case(&u));
+            react(reduced).len()
+        })
+        .min()
+        .unwrap_or(polymer.len())
+}
+
+/// React a polymer in a single pass, keeping the units that have survived so
+/// far on a stack. Each new unit either reacts with the top of the stack or
+/// is pushed onto it.
+pub fn react(polymer: impl IntoIterator<Item = u8>) -> Vec<u8> {
+    let mut stack = Vec::new();
+
+    for unit in polymer {
+        match stack.last() {
+            Some(&top) if units_react(top, unit) => {
+                stack.pop();
             }
-        } else {
-            i += 1;
+            _ => stack.push(unit),
         }
     }
 
-    polymer
+    stack
 }
 
-fn units_react(a: u8, b: u8) -> bool {
+pub fn units_react(a: u8, b: u8) -> bool {
     (a ^ b) == 32
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::MAX_DIAGNOSTICS;
+
+    const EXAMPLE: &str = "dabAcCaCBAcCcaDA";
+
+    #[test]
+    fn react_example() {
+        assert_eq!(react(EXAMPLE.as_bytes().to_owned()), b"dabCBAcaDA");
+        assert_eq!(react(b"aA".to_vec()), b"");
+        assert_eq!(react(b"abBA".to_vec()), b"");
+        assert_eq!(react(b"abAB".to_vec()), b"abAB");
+        assert_eq!(react(b"aabAAB".to_vec()), b"aabAAB");
+    }
+
+    #[test]
+    fn examples() {
+        assert_eq!(part1(EXAMPLE.as_bytes()), 10);
+        assert_eq!(part2(EXAMPLE.as_bytes()), 4);
+        assert_eq!(part2(b"aA"), 0);
+        assert_eq!(part2(b""), 0);
+
+        let day = Day5::parse(EXAMPLE).unwrap();
+        assert_eq!(day.reacted, b"dabCBAcaDA");
+        assert_eq!(day.part1().unwrap(), 10);
+        assert_eq!(day.part2().unwrap(), 4);
+    }
+
+    #[test]
+    fn long_polymer() {
+        let n = 1_000_000;
+        let mut polymer = b"ab".repeat(n);
+        polymer.extend(b"BA".repeat(n));
+        polymer.push(b'c');
+
+        let day = Day5::parse(std::str::from_utf8(&polymer).unwrap()).unwrap();
+        assert_eq!(day.reacted, b"c");
+        assert_eq!(day.part2().unwrap(), 0);
+    }
+
+    #[test]
+    fn rejects_non_letters() {
+        assert!(Day5::parse("dabA1cC").is_err());
+    }
+
+    #[test]
+    fn wrapped_polymer() {
+        let day = Day5::parse("dabAcCa\nCBAcCcaDA\n").unwrap();
+        assert_eq!(day.part1().unwrap(), 10);
+
+        let line = format!("{}\n", "ab1".repeat(30));
+        let errors = Day5::parse(&line.repeat(100))
+            .err()
+            .unwrap()
+            .downcast::<ParseErrors>()
+            .unwrap();
+        assert_eq!(errors.diagnostics.len(), MAX_DIAGNOSTICS);
+        assert_eq!(errors.omitted, 100 - MAX_DIAGNOSTICS);
+        assert_eq!(errors.diagnostics[1].line, 2);
+        assert_eq!(errors.diagnostics[1].column, 3);
+        assert_eq!(errors.diagnostics[1].text, line.trim_end());
+    }
+
+    #[test]
+    fn real_input() {
+        let day = Day5::parse(INPUT).unwrap();
+        assert_eq!(day.part1().unwrap(), 10888);
+        assert_eq!(day.part2().unwrap(), 6952);
+    }
+}
@@ -2,33 +2,61 @@ use std::collections::HashMap;
 use std::str::FromStr;
 
 use failure::{err_msg, Error};
-use regex::Regex;
 
-pub fn solve() {
-    let lines: Vec<Point> = include_str!("data/day6.txt")
-        .lines()
-        .map(|s| s.parse().unwrap())
-        .collect();
+use crate::parse::{parse_lines, FieldError, Pattern};
+use crate::solution::Solution;
 
-    println!("Part 1: {}", part1(&lines));
-    println!("Part 2: {}", part2(&lines));
+pub const INPUT: &str = include_str!("data/day6.txt");
+
+pub struct Day6 {
+    pub lines: Vec<Point>,
 }
 
-fn part1(lines: &[Point]) -> i32 {
+impl Solution for Day6 {
+    type Part1 = i32;
+    type Part2 = i32;
+
+    fn parse(input: &str) -> Result<Self, Error> {
+        let lines = parse_lines(input)?;
+
+        Ok(Day6 { lines })
+    }
+
+    fn part1(&self) -> Result<i32, Error> {
+        if self.lines.is_empty() {
+            return Err(err_msg("There are no points in the input"));
+        }
+        part1(&self.lines).ok_or_else(|| err_msg("Every point has an infinite area"))
+    }
+
+    fn part2(&self) -> Result<i32, Error> {
+        part2(&self.lines).ok_or_else(|| err_msg("There are no points in the input"))
+    }
+}
+
+/// The smallest and largest x and y coordinates, or `None` with no points.
+fn spans(lines: &[Point]) -> Option<((i32, i32), (i32, i32))> {
     let x_span = (
-        lines.iter().map(|p| p.x).min().unwrap(),
-        lines.iter().map(|p| p.x).max().unwrap(),
+        lines.iter().map(|p| p.x).min()?,
+        lines.iter().map(|p| p.x).max()?,
     );
     let y_span = (
-        lines.iter().map(|p| p.y).min().unwrap(),
-        lines.iter().map(|p| p.y).max().unwrap(),
+        lines.iter().map(|p| p.y).min()?,
+        lines.iter().map(|p| p.y).max()?,
     );
 
+    Some((x_span, y_span))
+}
+
+/// The largest finite area, or `None` if no point has one.
+pub fn part1(lines: &[Point]) -> Option<i32> {
+    let (x_span, y_span) = spans(lines)?;
+
     let mut grid = Part1Grid::new(
         x_span.0 as usize,
         y_span.0 as usize,
-        (x_span.1 - x_span.0) as usize,
-        (y_span.1 - y_span.0) as usize,
+        (x_span.1 - x_span.0 + 1) as usize,
+        (y_span.1 - y_span.0 + 1) as usize,
     );
 
     for (id, point) in lines.iter().enumerate() {
@@ -36,40 +64,39 @@ fn part1(lines: &[Point]) -> i32 {
     }
 
     let areas = grid.areas();
-    *areas.values().max().unwrap()
+    areas.values().max().cloned()
 }
 
-fn part2(lines: &[Point]) -> i32 {
-    let x_span = (
-        lines.iter().map(|p| p.x).min().unwrap(),
-        lines.iter().map(|p| p.x).max().unwrap(),
-    );
-    let y_span = (
-        lines.iter().map(|p| p.y).min().unwrap(),
-        lines.iter().map(|p| p.y).max().unwrap(),
-    );
+pub fn part2(lines: &[Point]) -> Option<i32> {
+    safe_area(lines, 10000)
+}
+
+/// The number of locations whose total distance to every point is less than
+/// `max_dist`, or `None` if there are no points.
+pub fn safe_area(lines: &[Point], max_dist: i32) -> Option<i32> {
+    let (x_span, y_span) = spans(lines)?;
 
     let mut grid = Part2Grid::new(
         x_span.0 as usize,
         y_span.0 as usize,
-        (x_span.1 - x_span.0) as usize,
-        (y_span.1 - y_span.0) as usize,
+        (x_span.1 - x_span.0 + 1) as usize,
+        (y_span.1 - y_span.0 + 1) as usize,
     );
 
     for point in lines {
         grid.add(point);
     }
 
-    grid.area(10000)
+    Some(grid.area(max_dist))
 }
 
 #[derive(Debug)]
-struct Point {
-    x: i32,
-    y: i32,
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
 }
 
-struct Part1Grid {
+pub struct Part1Grid {
     left: usize,
     top: usize,
     width: usize,
@@ -79,18 +106,18 @@ struct Part1Grid {
 }
 
 impl Part1Grid {
-    fn new(left: usize, top: usize, width: usize, height: usize) -> Part1Grid {
+    pub fn new(left: usize, top: usize, width: usize, height: usize) -> Part1Grid {
         Part1Grid {
             left,
             top,
             width,
             height,
-            dists: vec![std::i32::MAX; width * height].into_boxed_slice(),
-            ids: vec![std::usize::MAX; width * height].into_boxed_slice(),
+            dists: vec![i32::MAX; width * height].into_boxed_slice(),
+            ids: vec![usize::MAX; width * height].into_boxed_slice(),
         }
     }
 
-    fn add(&mut self, id: usize, point: &Point) {
+    pub fn add(&mut self, id: usize, point: &Point) {
         for x in self.left..(self.left + self.width) {
             for y in self.top..(self.top + self.height) {
                 let dist = (point.x - x as i32).abs() + (point.y - y as i32).abs();
@@ -100,13 +127,13 @@ impl Part1Grid {
                     self.dists[idx] = dist;
                     self.ids[idx] = id;
                 } else if self.dists[idx] == dist {
-                    self.ids[idx] = std::usize::MAX;
+                    self.ids[idx] = usize::MAX;
                 }
             }
         }
     }
 
-    fn areas(&self) -> HashMap<usize, i32> {
+    pub fn areas(&self) -> HashMap<usize, i32> {
         let mut result = HashMap::new();
 
         for i in 0..(self.width * self.height) {
@@ -122,12 +149,12 @@ impl Part1Grid {
             result.remove(&self.ids[i * self.width + (self.width - 1)]);
         }
 
-        result.remove(&std::usize::MAX);
+        result.remove(&usize::MAX);
         result
     }
 }
 
-struct Part2Grid {
+pub struct Part2Grid {
     left: usize,
     top: usize,
     width: usize,
@@ -136,7 +163,7 @@ struct Part2Grid {
 }
 
 impl Part2Grid {
-    fn new(left: usize, top: usize, width: usize, height: usize) -> Part2Grid {
+    pub fn new(left: usize, top: usize, width: usize, height: usize) -> Part2Grid {
         Part2Grid {
             left,
             top,
@@ -146,7 +173,7 @@ impl Part2Grid {
         }
     }
 
-    fn add(&mut self, point: &Point) {
+    pub fn add(&mut self, point: &Point) {
         for x in self.left..(self.left + self.width) {
             for y in self.top..(self.top + self.height) {
                 let dist = (point.x - x as i32).abs() + (point.y - y as i32).abs();
@@ -156,29 +183,62 @@ impl Part2Grid {
         }
     }
 
-    fn area(&self, max_dist: i32) -> i32 {
+    pub fn area(&self, max_dist: i32) -> i32 {
         self.dists.iter().filter(|d| **d < max_dist).count() as i32
     }
 }
 
+static POINT: Pattern = Pattern::new(&[
+    (r"(\d+)", "the x coordinate"),
+    (", ", "`, `"),
+    (r"(\d+)", "the y coordinate"),
+]);
+
 impl FromStr for Point {
-    type Err = Error;
+    type Err = FieldError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let re = Regex::new(r"(\d+), (\d+)").unwrap();
-        let caps = re.captures(s).ok_or_else(|| err_msg("Invalid format"))?;
-
-        let get = |idx| -> Result<i32, Self::Err> {
-            Ok(caps
-                .get(idx)
-                .ok_or_else(|| err_msg("Invalid format"))?
-                .as_str()
-                .parse()?)
-        };
+        let fields = POINT.captures(s)?;
 
         Ok(Point {
-            x: get(1)?,
-            y: get(2)?,
+            x: fields.get(1)?,
+            y: fields.get(2)?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "1, 1\n1, 6\n8, 3\n3, 4\n5, 5\n8, 9\n";
+
+    #[test]
+    fn part1_example() {
+        let day = Day6::parse(EXAMPLE).unwrap();
+        assert_eq!(day.part1().unwrap(), 17);
+    }
+
+    #[test]
+    fn part2_example() {
+        let day = Day6::parse(EXAMPLE).unwrap();
+        assert_eq!(safe_area(&day.lines, 32), Some(16));
+    }
+
+    #[test]
+    fn no_points() {
+        let day = Day6::parse("").unwrap();
+        assert!(day.part1().is_err());
+        assert!(day.part2().is_err());
+
+        let day = Day6::parse("1, 1\n5, 5\n").unwrap();
+        assert_eq!(part1(&day.lines), None);
+    }
+
+    #[test]
+    fn real_input() {
+        let day = Day6::parse(INPUT).unwrap();
+        assert_eq!(day.part1().unwrap(), 2342);
+        assert_eq!(day.part2().unwrap(), 43302);
+    }
+}
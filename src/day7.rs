@@ -1,19 +1,36 @@
 use std::str::FromStr;
 
-use failure::{err_msg, Error};
-use regex::Regex;
+use failure::Error;
 
-pub fn solve() {
-    let lines: Vec<Instruction> = include_str!("data/day7.txt")
-        .lines()
-        .map(|s| s.parse().unwrap())
-        .collect();
+use crate::parse::{parse_lines, FieldError, Pattern};
+use crate::solution::Solution;
 
-    println!("Part 1: {}", part1(&lines));
-    println!("Part 2: {}", part2(&lines));
+pub const INPUT: &str = include_str!("data/day7.txt");
+
+pub struct Day7 {
+    pub lines: Vec<Instruction>,
+}
+
+impl Solution for Day7 {
+    type Part1 = String;
+    type Part2 = u32;
+
+    fn parse(input: &str) -> Result<Self, Error> {
+        let lines = parse_lines(input)?;
+
+        Ok(Day7 { lines })
+    }
+
+    fn part1(&self) -> Result<String, Error> {
+        Ok(part1(&self.lines))
+    }
+
+    fn part2(&self) -> Result<u32, Error> {
+        Ok(part2(&self.lines))
+    }
 }
 
-fn part1(lines: &[Instruction]) -> String {
+pub fn part1(lines: &[Instruction]) -> String {
     let mut sleigh = Sleigh::new(lines);
 
     while let Some(step) = sleigh.next_step() {
@@ -23,15 +40,21 @@ fn part1(lines: &[Instruction]) -> String {
     sleigh.sequence()
 }
 
-fn part2(lines: &[Instruction]) -> u32 {
+pub fn part2(lines: &[Instruction]) -> u32 {
+    assemble(lines, 5, 60)
+}
+
+/// The time taken for `workers` elves to complete every step, where each step
+/// takes `base_duration` seconds plus its position in the alphabet.
+pub fn assemble(lines: &[Instruction], workers: usize, base_duration: u32) -> u32 {
     let mut sleigh = Sleigh::new(lines);
-    let mut helpers = Helpers::new(5);
+    let mut helpers = Helpers::new(workers);
     let mut time = 0;
 
     while !sleigh.done() || !helpers.done() {
         while let Some(elf) = helpers.get_idle() {
             if let Some(step) = sleigh.next_step() {
-                let duration = step as u32 - 'A' as u32 + 61;
+                let duration = step as u32 - 'A' as u32 + 1 + base_duration;
                 elf.give(step, duration);
             } else {
                 break;
@@ -48,18 +71,19 @@ fn part2(lines: &[Instruction]) -> u32 {
     time
 }
 
-#[derive(Debug, Clone)]
-enum Elf {
+#[derive(Debug, Clone, Default)]
+pub enum Elf {
+    #[default]
     Idle,
     Busy(char, u32),
 }
 
 impl Elf {
-    fn new() -> Elf {
+    pub fn new() -> Elf {
         Elf::Idle
     }
 
-    fn work(&mut self) -> Option<char> {
+    pub fn work(&mut self) -> Option<char> {
         if let &mut Elf::Busy(c, t) = self {
             if t == 1 {
                 *self = Elf::Idle;
@@ -72,66 +96,63 @@ impl Elf {
         None
     }
 
-    fn give(&mut self, step: char, t: u32) {
+    pub fn give(&mut self, step: char, t: u32) {
         *self = Elf::Busy(step, t)
     }
 
-    fn is_idle(&self) -> bool {
-        if let Elf::Idle = self {
-            true
-        } else {
-            false
-        }
+    pub fn is_idle(&self) -> bool {
+        matches!(self, Elf::Idle)
     }
 }
 
 #[derive(Debug)]
-struct Helpers {
+pub struct Helpers {
     elves: Vec<Elf>,
 }
 
 impl Helpers {
-    fn new(count: usize) -> Helpers {
+    pub fn new(count: usize) -> Helpers {
         Helpers {
             elves: vec![Elf::new(); count],
         }
     }
 
-    fn get_idle(&mut self) -> Option<&mut Elf> {
-        for elf in &mut self.elves {
-            if elf.is_idle() {
-                return Some(elf);
-            }
-        }
-
-        None
+    pub fn get_idle(&mut self) -> Option<&mut Elf> {
+        self.elves.iter_mut().find(|elf| elf.is_idle())
     }
 
-    fn work(&mut self) -> Vec<char> {
+    pub fn work(&mut self) -> Vec<char> {
         self.elves.iter_mut().filter_map(Elf::work).collect()
     }
 
-    fn done(&self) -> bool {
+    pub fn done(&self) -> bool {
         self.elves.iter().all(Elf::is_idle)
     }
 }
 
-struct Sleigh {
+pub struct Sleigh {
     instructions: Vec<Instruction>,
     remaining: Vec<char>,
     done: Vec<char>,
 }
 
 impl Sleigh {
-    fn new(instructions: &[Instruction]) -> Sleigh {
+    pub fn new(instructions: &[Instruction]) -> Sleigh {
+        let mut remaining: Vec<char> = instructions
+            .iter()
+            .flat_map(|instr| vec![instr.0, instr.1])
+            .collect();
+        remaining.sort();
+        remaining.dedup();
+
         Sleigh {
             instructions: instructions.to_vec(),
-            remaining: "ABCDEFGHIJKLMNOPQRSTUVWXYZ".chars().collect(),
+            remaining,
             done: Vec::new(),
         }
     }
 
-    fn next_step(&mut self) -> Option<char> {
+    pub fn next_step(&mut self) -> Option<char> {
         'outer: for i in 0..self.remaining.len() {
             let step = self.remaining[i];
 
@@ -148,37 +169,68 @@ impl Sleigh {
         None
     }
 
-    fn complete(&mut self, step: char) {
+    pub fn complete(&mut self, step: char) {
         self.done.push(step)
     }
 
-    fn sequence(&self) -> String {
+    pub fn sequence(&self) -> String {
         self.done.iter().collect()
     }
 
-    fn done(&self) -> bool {
+    pub fn done(&self) -> bool {
         self.remaining.is_empty()
     }
 }
 
 #[derive(Debug, Clone)]
-struct Instruction(char, char);
+pub struct Instruction(pub char, pub char);
+
+static INSTRUCTION: Pattern = Pattern::new(&[
+    ("Step ", "`Step `"),
+    ("([A-Z])", "a step letter from A to Z"),
+    (
+        " must be finished before step ",
+        "` must be finished before step `",
+    ),
+    ("([A-Z])", "a step letter from A to Z"),
+    (r" can begin\.", "` can begin.`"),
+]);
 
 impl FromStr for Instruction {
-    type Err = Error;
+    type Err = FieldError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let re = Regex::new(r"Step (.) must be finished before step (.) can begin\.").unwrap();
-        let caps = re.captures(s).ok_or_else(|| err_msg("Invalid format"))?;
-
-        let get = |idx| -> Result<char, Self::Err> {
-            Ok(caps
-                .get(idx)
-                .ok_or_else(|| err_msg("Invalid format"))?
-                .as_str()
-                .parse()?)
-        };
-
-        Ok(Instruction(get(1)?, get(2)?))
+        let fields = INSTRUCTION.captures(s)?;
+
+        Ok(Instruction(fields.get(1)?, fields.get(2)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Step C must be finished before step A can begin.
+Step C must be finished before step F can begin.
+Step A must be finished before step B can begin.
+Step A must be finished before step D can begin.
+Step B must be finished before step E can begin.
+Step D must be finished before step E can begin.
+Step F must be finished before step E can begin.
+";
+
+    #[test]
+    fn examples() {
+        let day = Day7::parse(EXAMPLE).unwrap();
+        assert_eq!(part1(&day.lines), "CABDFE");
+        assert_eq!(assemble(&day.lines, 2, 0), 15);
+    }
+
+    #[test]
+    fn real_input() {
+        let day = Day7::parse(INPUT).unwrap();
+        assert_eq!(day.part1().unwrap(), "JNOIKSYABEQRUVWXGTZFDMHLPC");
+        assert_eq!(day.part2().unwrap(), 1099);
     }
 }
@@ -1,60 +1,123 @@
 use std::collections::VecDeque;
 
-pub fn solve() {
-    let node: Node = include_str!("data/day8.txt")
-        .trim()
-        .split(' ')
-        .map(|s| s.parse().unwrap())
-        .collect::<VecDeque<i32>>()
-        .into();
-
-    println!("Part 1: {}", part1(&node));
-    println!("Part 2: {}", part2(&node));
+use failure::Error;
+
+use crate::parse::{column, FieldError, ParseErrors};
+use crate::solution::Solution;
+
+pub const INPUT: &str = include_str!("data/day8.txt");
+
+pub struct Day8 {
+    pub node: Node,
 }
 
-fn part1(node: &Node) -> i32 {
-    node.metadata_sum()
+impl Solution for Day8 {
+    type Part1 = i32;
+    type Part2 = i32;
+
+    fn parse(input: &str) -> Result<Self, Error> {
+        let lines: Vec<&str> = input.lines().collect();
+
+        let mut spec = VecDeque::new();
+        // The line index and byte offset of each number, for reporting errors.
+        let mut starts = Vec::new();
+        let mut errors = ParseErrors::new(Vec::new());
+
+        for (idx, line) in lines.iter().enumerate() {
+            let mut start = 0;
+
+            for token in line.split(' ') {
+                if !token.is_empty() {
+                    match token.parse() {
+                        Ok(n) => {
+                            spec.push_back(n);
+                            starts.push((idx, start));
+                        }
+                        Err(e) => {
+                            let error =
+                                FieldError::new(column(line, start), format!("a number ({})", e));
+                            errors.push(idx + 1, line, error);
+                            break;
+                        }
+                    }
+                }
+
+                start += token.len() + 1;
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors.into());
+        }
+
+        let node = Node::from_spec(&mut spec).ok_or_else(|| {
+            let idx = lines.len().saturating_sub(1);
+            let line = lines.get(idx).cloned().unwrap_or("");
+            let error = FieldError::new(
+                column(line, line.len()),
+                "more numbers to complete the tree",
+            );
+            ParseErrors::single(idx + 1, line, error)
+        })?;
+
+        if !spec.is_empty() {
+            let (idx, start) = starts[starts.len() - spec.len()];
+            let error = FieldError::new(
+                column(lines[idx], start),
+                "end of input after the root node",
+            );
+            return Err(ParseErrors::single(idx + 1, lines[idx], error).into());
+        }
+
+        Ok(Day8 { node })
+    }
+
+    fn part1(&self) -> Result<i32, Error> {
+        Ok(part1(&self.node))
+    }
+
+    fn part2(&self) -> Result<i32, Error> {
+        Ok(part2(&self.node))
+    }
 }
 
-fn part2(node: &Node) -> i32 {
-    node.value()
+pub fn part1(node: &Node) -> i32 {
+    node.metadata_sum()
 }
 
-struct Node {
-    metadata: Vec<i32>,
-    children: Vec<Node>,
+pub fn part2(node: &Node) -> i32 {
+    node.value()
 }
 
-impl From<VecDeque<i32>> for Node {
-    fn from(mut spec: VecDeque<i32>) -> Node {
-        Node::from_spec(&mut spec)
-    }
+pub struct Node {
+    pub metadata: Vec<i32>,
+    pub children: Vec<Node>,
 }
 
 impl Node {
-    fn from_spec(spec: &mut VecDeque<i32>) -> Node {
-        let n_children = spec.pop_front().unwrap();
-        let n_metadata = spec.pop_front().unwrap();
+    pub fn from_spec(spec: &mut VecDeque<i32>) -> Option<Node> {
+        let n_children = spec.pop_front()?;
+        let n_metadata = spec.pop_front()?;
 
         let mut children = Vec::new();
         for _ in 0..n_children {
-            children.push(Node::from_spec(spec));
+            children.push(Node::from_spec(spec)?);
         }
 
         let mut metadata = Vec::new();
         for _ in 0..n_metadata {
-            metadata.push(spec.pop_front().unwrap());
+            metadata.push(spec.pop_front()?);
         }
 
-        Node { metadata, children }
+        Some(Node { metadata, children })
     }
 
-    fn metadata_sum(&self) -> i32 {
+    pub fn metadata_sum(&self) -> i32 {
         self.metadata.iter().sum::<i32>()
             + self.children.iter().map(|c| c.metadata_sum()).sum::<i32>()
     }
 
-    fn value(&self) -> i32 {
+    pub fn value(&self) -> i32 {
         if self.children.is_empty() {
             self.metadata.iter().sum()
         } else {
@@ -70,3 +133,58 @@ impl Node {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "2 3 0 3 10 11 12 1 1 0 1 99 2 1 1 2";
+
+    #[test]
+    fn examples() {
+        let day = Day8::parse(EXAMPLE).unwrap();
+        assert_eq!(day.part1().unwrap(), 138);
+        assert_eq!(day.part2().unwrap(), 66);
+    }
+
+    #[test]
+    fn rejects_incomplete_tree() {
+        assert!(Day8::parse("2 3 0 3 10 11 12 1 1 0 1 99 2 1 1").is_err());
+        assert!(Day8::parse("0 1 5 7").is_err());
+    }
+
+    #[test]
+    fn wrapped_spec() {
+        let day = Day8::parse("2 3 0 3 10 11 12\n1 1 0 1 99 2\n1 1 2\n").unwrap();
+        assert_eq!(day.part1().unwrap(), 138);
+
+        let errors = Day8::parse("0 2\n5 x y\n")
+            .err()
+            .unwrap()
+            .downcast::<ParseErrors>()
+            .unwrap();
+        assert_eq!(errors.diagnostics.len(), 1);
+        assert_eq!(
+            (errors.diagnostics[0].line, errors.diagnostics[0].column),
+            (2, 3)
+        );
+        assert_eq!(errors.diagnostics[0].text, "5 x y");
+
+        let errors = Day8::parse("0 1 5\n7\n")
+            .err()
+            .unwrap()
+            .downcast::<ParseErrors>()
+            .unwrap();
+        assert_eq!(
+            (errors.diagnostics[0].line, errors.diagnostics[0].column),
+            (2, 1)
+        );
+    }
+
+    #[test]
+    fn real_input() {
+        let day = Day8::parse(INPUT).unwrap();
+        assert_eq!(day.part1().unwrap(), 43351);
+        assert_eq!(day.part2().unwrap(), 21502);
+    }
+}
@@ -1,20 +1,41 @@
 use std::str::FromStr;
 
-use failure::{err_msg, Error};
-use regex::Regex;
+use failure::Error;
 
-pub fn solve() {
-    let game_setup: GameSetup = include_str!("data/day9.txt").trim().parse().unwrap();
-    println!("Part 1: {}", part1(game_setup));
-    println!("Part 2: {}", part2(game_setup));
+use crate::parse::{parse_line, FieldError, Pattern};
+use crate::solution::Solution;
+
+pub const INPUT: &str = include_str!("data/day9.txt");
+
+pub struct Day9 {
+    pub game_setup: GameSetup,
+}
+
+impl Solution for Day9 {
+    type Part1 = u32;
+    type Part2 = u32;
+
+    fn parse(input: &str) -> Result<Self, Error> {
+        let game_setup = parse_line(input)?;
+
+        Ok(Day9 { game_setup })
+    }
+
+    fn part1(&self) -> Result<u32, Error> {
+        Ok(part1(self.game_setup))
+    }
+
+    fn part2(&self) -> Result<u32, Error> {
+        Ok(part2(self.game_setup))
+    }
 }
 
-fn part1(game_setup: GameSetup) -> u32 {
+pub fn part1(game_setup: GameSetup) -> u32 {
     let mut game = Game::new(game_setup);
     game.play()
 }
 
-fn part2(game_setup: GameSetup) -> u32 {
+pub fn part2(game_setup: GameSetup) -> u32 {
     let game_setup = GameSetup {
         num_marbles: 100 * game_setup.num_marbles,
         ..game_setup
@@ -35,7 +56,7 @@ impl Marble {
     }
 }
 
-struct Game {
+pub struct Game {
     setup: GameSetup,
     board: Vec<Marble>,
     scores: Vec<u32>,
@@ -45,9 +66,9 @@ struct Game {
 }
 
 impl Game {
-    fn new(setup: GameSetup) -> Game {
+    pub fn new(setup: GameSetup) -> Game {
         Game {
-            setup: setup,
+            setup,
             board: vec![Marble::new(); (setup.num_marbles + 1) as usize],
             scores: vec![0; setup.num_players as usize],
             current_idx: 0,
@@ -56,19 +77,19 @@ impl Game {
         }
     }
 
-    fn left(&mut self, count: usize) {
+    pub fn left(&mut self, count: usize) {
         for _ in 0..count {
             self.current_idx = self.board[self.current_idx].left;
         }
     }
 
-    fn right(&mut self, count: usize) {
+    pub fn right(&mut self, count: usize) {
         for _ in 0..count {
             self.current_idx = self.board[self.current_idx].right;
         }
     }
 
-    fn remove(&mut self) -> u32 {
+    pub fn remove(&mut self) -> u32 {
         let idx = self.current_idx;
         let left_idx = self.board[idx].left;
         let right_idx = self.board[idx].right;
@@ -83,7 +104,7 @@ impl Game {
         idx as u32
     }
 
-    fn insert_right(&mut self, idx: u32) {
+    pub fn insert_right(&mut self, idx: u32) {
         let idx = idx as usize;
         let left_idx = self.current_idx;
         let right_idx = self.board[left_idx].right;
@@ -97,7 +118,7 @@ impl Game {
         self.current_idx = idx;
     }
 
-    fn play(&mut self) -> u32 {
+    pub fn play(&mut self) -> u32 {
         while !self.done() {
             self.step();
         }
@@ -105,11 +126,13 @@ impl Game {
         self.highest_score()
     }
 
-    fn highest_score(&self) -> u32 {
+    pub fn highest_score(&self) -> u32 {
         *self.scores.iter().max().unwrap()
     }
 
-    fn step(&mut self) {
+    // `is_multiple_of` would need Rust 1.87.
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn step(&mut self) {
         if self.next_marble % 23 != 0 {
             self.right(1);
             self.insert_right(self.next_marble);
@@ -122,35 +145,92 @@ impl Game {
         self.next_player = (self.next_player + 1) % self.setup.num_players;
     }
 
-    fn done(&self) -> bool {
-        self.next_marble >= self.setup.num_marbles
+    pub fn done(&self) -> bool {
+        self.next_marble > self.setup.num_marbles
     }
 }
 
 #[derive(Copy, Clone)]
-struct GameSetup {
-    num_players: u32,
-    num_marbles: u32,
+pub struct GameSetup {
+    pub num_players: u32,
+    pub num_marbles: u32,
 }
 
+static GAME_SETUP: Pattern = Pattern::new(&[
+    (r"(\d+)", "the number of players"),
+    (
+        " players; last marble is worth ",
+        "` players; last marble is worth `",
+    ),
+    (r"(\d+)", "the value of the last marble"),
+    (" points", "` points`"),
+]);
+
 impl FromStr for GameSetup {
-    type Err = Error;
+    type Err = FieldError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let re = Regex::new(r"(\d+) players; last marble is worth (\d+) points").unwrap();
-        let caps = re.captures(s).ok_or_else(|| err_msg("Invalid format"))?;
+        let fields = GAME_SETUP.captures(s)?;
 
-        let get = |idx| -> Result<u32, Self::Err> {
-            Ok(caps
-                .get(idx)
-                .ok_or_else(|| err_msg("Invalid format"))?
-                .as_str()
-                .parse()?)
-        };
+        let num_players = fields.get(1)?;
+        if num_players == 0 {
+            return Err(fields.error(1, "at least one player"));
+        }
 
         Ok(GameSetup {
-            num_players: get(1)?,
-            num_marbles: get(2)?,
+            num_players,
+            num_marbles: fields.get(2)?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn high_score(setup: &str) -> u32 {
+        let setup: GameSetup = setup.parse().unwrap();
+        Game::new(setup).play()
+    }
+
+    #[test]
+    fn examples() {
+        assert_eq!(high_score("9 players; last marble is worth 25 points"), 32);
+        assert_eq!(
+            high_score("10 players; last marble is worth 1618 points"),
+            8317
+        );
+        assert_eq!(
+            high_score("13 players; last marble is worth 7999 points"),
+            146373
+        );
+        assert_eq!(
+            high_score("17 players; last marble is worth 1104 points"),
+            2764
+        );
+        assert_eq!(
+            high_score("21 players; last marble is worth 6111 points"),
+            54718
+        );
+        assert_eq!(
+            high_score("30 players; last marble is worth 5807 points"),
+            37305
+        );
+    }
+
+    #[test]
+    fn no_players() {
+        let error = "0 players; last marble is worth 25 points"
+            .parse::<GameSetup>()
+            .err()
+            .unwrap();
+        assert_eq!(error, FieldError::new(1, "at least one player"));
+    }
+
+    #[test]
+    fn real_input() {
+        let day = Day9::parse(INPUT).unwrap();
+        assert_eq!(day.part1().unwrap(), 424639);
+        assert_eq!(day.part2().unwrap(), 3516007333);
+    }
+}
@@ -0,0 +1,21 @@
+//! Solutions to the Advent of Code 2018 puzzles.
+//!
+//! Each `dayN` module exposes its puzzle input parser, the types it parses
+//! into and the functions that solve both parts. The [`solution`] module ties
+//! them together behind a common `Solution` trait and a registry of days.
+
+pub mod answers;
+pub mod bench;
+pub mod day1;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+pub mod day10;
+pub mod image;
+pub mod parse;
+pub mod solution;
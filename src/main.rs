@@ -1,31 +1,102 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::panic;
+use std::path::Path;
+use std::time::Duration;
+
 use docopt::Docopt;
-use serde_derive::Deserialize;
-
-mod day1;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
-mod day10;
+use failure::{err_msg, Error, ResultExt};
+use serde_derive::{Deserialize, Serialize};
+
+use advent_of_code_2018::answers::{self, AnswerFile, Check};
+use advent_of_code_2018::bench::Bench;
+use advent_of_code_2018::day1::{self, Day1};
+use advent_of_code_2018::day3::{self, Claim, Day3, Engine, OverlapReport};
+use advent_of_code_2018::day4::{self, Bucket, GuardSummary, Mode, Pick};
+use advent_of_code_2018::parse::ParseErrors;
+use advent_of_code_2018::solution::{self, Answer, Day, Parts, Run, Solution};
 
 const USAGE: &str = "
 Advent of code 2018
 
 Usage:
-    advent-of-code-2018 <day>
+    advent-of-code-2018 all [--part <n>] [--time] [--format <fmt>]
+    advent-of-code-2018 verify [--answers <path>] [--record]
+    advent-of-code-2018 trace [--input <path>]
+    advent-of-code-2018 overlaps [--input <path>] [--format <fmt>]
+    advent-of-code-2018 heatmap <image> [--input <path>]
+    advent-of-code-2018 guards [--input <path>] [--format <fmt>] [--bucket <b>] [--lenient] [--ties]
+    advent-of-code-2018 bench <day> [--input <path>] [--part <n>] [--iterations <n>]
+    advent-of-code-2018 <day> [--input <path>] [--part <n>] [--time] [--format <fmt>]
     advent-of-code-2018 (-h | --help)
 
 Options:
-    -h --help   Show this help
+    -h --help             Show this help
+    -i --input <path>     Read the puzzle input from <path> instead of the
+                          embedded input, or from stdin if <path> is -
+    -p --part <n>         Only run part <n>, either 1 or 2
+    -t --time             Report how long parsing and each part took
+    -f --format <fmt>     Output format, either text or json, or csv for the
+                          guards command. JSON output has one object per
+                          line for each day [default: text]
+    -n --iterations <n>   Number of times to repeat each stage [default: 10]
+    --answers <path>      The answers file to verify against, by default
+                          src/answers.toml in the source tree
+    --record              Write the current answers into the answers file
+    --bucket <b>          Count guards' time asleep in each minute of the
+                          midnight hour, each minute of the day or each hour
+                          of the day: midnight, minute or hour
+                          [default: midnight]
+    --lenient             Repair inconsistent guard logs instead of failing
+    --ties                List every guard and minute tied for each strategy
+
+The trace command prints day 1's frequency after every change as CSV, up to
+the first frequency reached twice.
+
+The overlaps command lists the other day 3 claims that each claim overlaps,
+with the square inches they share, and the claims that overlap nothing.
+
+The heatmap command draws the day 3 fabric coloured by the number of claims on
+each square, with uncontested claims outlined. It writes a PNG or PPM image,
+depending on whether <image> ends in .png or .ppm.
+
+The guards command summarises each day 4 guard's naps: the minutes they spent
+asleep, their shifts, their sleepiest minute and how often they slept through
+each minute of the midnight hour, or each bucket chosen with --bucket. Logs
+where guards wake without falling asleep, or sleep through the end of a shift,
+are rejected unless --lenient is given, in which case each repair is reported
+as a warning. Ties between guards go to the lowest id, and ties between
+minutes to the earliest.
 ";
 
 #[derive(Debug, Deserialize)]
 struct Args {
+    cmd_all: bool,
+    cmd_bench: bool,
+    cmd_trace: bool,
+    cmd_overlaps: bool,
+    cmd_heatmap: bool,
+    cmd_guards: bool,
+    cmd_verify: bool,
     arg_day: Option<u32>,
+    arg_image: Option<String>,
+    flag_input: Option<String>,
+    flag_part: Option<u32>,
+    flag_time: bool,
+    flag_format: String,
+    flag_iterations: usize,
+    flag_answers: Option<String>,
+    flag_record: bool,
+    flag_bucket: String,
+    flag_lenient: bool,
+    flag_ties: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Json,
+    Csv,
 }
 
 fn main() {
@@ -33,17 +104,718 @@ fn main() {
         .and_then(|d| d.deserialize())
         .unwrap_or_else(|e| e.exit());
 
-    match args.arg_day {
-        Some(1) => day1::solve(),
-        Some(2) => day2::solve(),
-        Some(3) => day3::solve(),
-        Some(4) => day4::solve(),
-        Some(5) => day5::solve(),
-        Some(6) => day6::solve(),
-        Some(7) => day7::solve(),
-        Some(8) => day8::solve(),
-        Some(9) => day9::solve(),
-        Some(10) => day10::solve(),
-        _ => println!("Unknown day"),
+    if let Err(e) = run(&args) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &Args) -> Result<(), Error> {
+    let parts = match args.flag_part {
+        None => Parts::Both,
+        Some(1) => Parts::Part1,
+        Some(2) => Parts::Part2,
+        Some(n) => return Err(err_msg(format!("There is no part {}, only 1 and 2", n))),
+    };
+
+    let format = match args.flag_format.as_str() {
+        "text" => Format::Text,
+        "json" => Format::Json,
+        "csv" if args.cmd_guards => Format::Csv,
+        format => return Err(err_msg(format!("Unknown output format {}", format))),
+    };
+    let json = format == Format::Json;
+
+    if args.cmd_all {
+        return run_all(parts, args.flag_time, json);
+    }
+
+    if args.cmd_verify {
+        let path = args
+            .flag_answers
+            .as_deref()
+            .unwrap_or(answers::DEFAULT_PATH);
+        return verify(Path::new(path), args.flag_record);
+    }
+
+    if args.cmd_trace {
+        let day1: Day1 = parse_day(1, day1::INPUT, &args.flag_input)?;
+
+        let stdout = io::stdout();
+        return ignore_broken_pipe(day1::trace(&day1.lines).write_csv(&mut stdout.lock()));
+    }
+
+    if args.cmd_overlaps {
+        let day3: Day3 = parse_day(3, day3::INPUT, &args.flag_input)?;
+        let report = OverlapReport::new(&day3.lines);
+
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        let result = if json {
+            serde_json::to_writer(&mut out, &report)
+                .map_err(io::Error::from)
+                .and_then(|_| writeln!(out))
+        } else {
+            write_overlaps(&mut out, &report)
+        };
+        return ignore_broken_pipe(result);
+    }
+
+    if args.cmd_heatmap {
+        let day3: Day3 = parse_day(3, day3::INPUT, &args.flag_input)?;
+        return write_heatmap(&day3.lines, args.arg_image.as_deref().unwrap_or(""));
+    }
+
+    if args.cmd_guards {
+        let name = input_name(4, &args.flag_input);
+        let input = day_input(day4::INPUT, &args.flag_input)?;
+        let mode = if args.flag_lenient {
+            Mode::Lenient
+        } else {
+            Mode::Strict
+        };
+
+        let (guards, anomalies) = day4::parse_log(&input)
+            .and_then(|records| day4::parse_records(&records, mode))
+            .map_err(|e| e.with_file(name.clone()))?;
+
+        for anomaly in &anomalies {
+            eprintln!(
+                "Warning: {}:{}: {} (expected {})",
+                name,
+                anomaly.line,
+                anomaly.text,
+                anomaly.expected()
+            );
+        }
+
+        let bucket = match args.flag_bucket.as_str() {
+            "midnight" => Bucket::MidnightMinute,
+            "minute" => Bucket::DayMinute,
+            "hour" => Bucket::Hour,
+            bucket => return Err(err_msg(format!("Unknown bucket {}", bucket))),
+        };
+
+        let summaries = day4::summaries(&guards, bucket);
+
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        let result = match format {
+            Format::Text => write_guards(&mut out, &summaries, bucket).and_then(|_| {
+                writeln!(out)?;
+                write_picks(&mut out, 1, &day4::strategy1(&guards), args.flag_ties)?;
+                write_picks(&mut out, 2, &day4::strategy2(&guards), args.flag_ties)
+            }),
+            Format::Json => summaries.iter().try_for_each(|summary| {
+                serde_json::to_writer(&mut out, summary)?;
+                writeln!(out)
+            }),
+            Format::Csv => write_guards_csv(&mut out, &summaries, bucket),
+        };
+        return ignore_broken_pipe(result);
+    }
+
+    let input = match &args.flag_input {
+        Some(path) => Some(read_input(path)?),
+        None => None,
+    };
+
+    let day = solution::find_day(args.arg_day.unwrap_or(0))?;
+    let input_name = input_name(day.number, &args.flag_input);
+    let input = input.as_deref().unwrap_or(day.input);
+
+    if args.cmd_bench {
+        if args.flag_iterations == 0 {
+            return Err(err_msg("--iterations must be at least 1"));
+        }
+
+        let bench = day
+            .bench(input, parts, args.flag_iterations)
+            .map_err(|e| name_input(e, input_name))?;
+
+        print_bench(day.number, &bench);
+        return Ok(());
+    }
+
+    let result = day.run(input, parts).map_err(|e| name_input(e, input_name));
+
+    if json {
+        print_json(day.number, &result)?;
+        return result.map(|_| ());
+    }
+
+    let run = result?;
+
+    if let Some(answer) = &run.answers.part1 {
+        print_answer(1, answer);
     }
+    if let Some(answer) = &run.answers.part2 {
+        print_answer(2, answer);
+    }
+
+    if args.flag_time {
+        println!();
+        println!("Parse:  {}", format_duration(run.timings.parse));
+        if let Some(time) = run.timings.part1 {
+            println!("Part 1: {}", format_duration(time));
+        }
+        if let Some(time) = run.timings.part2 {
+            println!("Part 2: {}", format_duration(time));
+        }
+    }
+
+    Ok(())
+}
+
+fn print_bench(day: u32, bench: &Bench) {
+    println!("Day {}, {} iterations", day, bench.iterations);
+    println!();
+
+    let mut rows = vec![vec![
+        "Stage".to_owned(),
+        "Min".to_owned(),
+        "Median".to_owned(),
+        "Max".to_owned(),
+    ]];
+
+    for (stage, stats) in &[
+        ("Parse", Some(bench.parse)),
+        ("Part 1", bench.part1),
+        ("Part 2", bench.part2),
+    ] {
+        let stats = match stats {
+            Some(stats) => stats,
+            None => continue,
+        };
+
+        rows.push(vec![
+            stage.to_string(),
+            format_duration(stats.min),
+            format_duration(stats.median),
+            format_duration(stats.max),
+        ]);
+    }
+
+    print_table(&rows);
+}
+
+fn write_overlaps(out: &mut impl Write, report: &OverlapReport) -> io::Result<()> {
+    let mut rows = vec![vec!["Claim".to_owned(), "Overlaps".to_owned()]];
+
+    for claim in &report.claims {
+        let overlaps: Vec<String> = claim
+            .overlaps
+            .iter()
+            .map(|o| format!("#{} ({})", o.id, o.area))
+            .collect();
+        rows.push(vec![
+            format!("#{}", claim.id),
+            if overlaps.is_empty() {
+                "-".to_owned()
+            } else {
+                overlaps.join(", ")
+            },
+        ]);
+    }
+
+    write_table(out, &rows)?;
+
+    let uncontested: Vec<String> = report
+        .uncontested
+        .iter()
+        .map(|id| format!("#{}", id))
+        .collect();
+    writeln!(out)?;
+    if uncontested.is_empty() {
+        writeln!(out, "Every claim overlaps another claim")
+    } else {
+        writeln!(out, "Uncontested: {}", uncontested.join(", "))
+    }
+}
+
+/// Write a table of the guards, then a chart of the minutes they slept
+/// through, laid out like the chart in the puzzle.
+fn write_guards(
+    out: &mut impl Write,
+    summaries: &[GuardSummary],
+    bucket: Bucket,
+) -> io::Result<()> {
+    let mut rows = vec![vec![
+        "Guard".to_owned(),
+        "Asleep".to_owned(),
+        "Shifts".to_owned(),
+        "Sleepiest".to_owned(),
+        "Times".to_owned(),
+    ]];
+
+    for summary in summaries {
+        rows.push(vec![
+            format!("#{}", summary.id),
+            summary.minutes_asleep.to_string(),
+            summary.shifts.to_string(),
+            bucket.start(summary.sleepiest),
+            summary.times.to_string(),
+        ]);
+    }
+
+    write_table(out, &rows)?;
+
+    if summaries.is_empty() {
+        return Ok(());
+    }
+
+    let width = summaries
+        .iter()
+        .map(|s| s.id.to_string().len() + 1)
+        .max()
+        .unwrap_or(0);
+    // Each bucket's label is written downwards, one digit per line.
+    let labels: Vec<Vec<char>> = (0..bucket.count())
+        .map(|idx| bucket.label(idx).chars().collect())
+        .collect();
+
+    writeln!(out)?;
+    for digit in 0..labels[0].len() {
+        let line: String = labels.iter().map(|l| l[digit]).collect();
+        writeln!(out, "{:w$}  {}", "", line, w = width)?;
+    }
+    for summary in summaries {
+        let chart: String = summary
+            .histogram
+            .iter()
+            .map(|&count| match count {
+                0 => '.',
+                1..=9 => (b'0' + count as u8) as char,
+                _ => '+',
+            })
+            .collect();
+        writeln!(
+            out,
+            "{:w$}  {}",
+            format!("#{}", summary.id),
+            chart,
+            w = width
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Write the pick made by one of the puzzle's strategies, and either every
+/// tied pick or how many there were.
+fn write_picks(out: &mut impl Write, strategy: u32, picks: &[Pick], ties: bool) -> io::Result<()> {
+    let describe = |p: &Pick| format!("guard #{} at 00:{:02} ({})", p.guard, p.minute, p.answer());
+
+    let (first, rest) = match picks.split_first() {
+        Some(picks) => picks,
+        None => return Ok(()),
+    };
+
+    write!(out, "Strategy {}: {}", strategy, describe(first))?;
+    match rest.len() {
+        0 => writeln!(out),
+        n if !ties => writeln!(
+            out,
+            ", tied with {} other pick{}",
+            n,
+            if n == 1 { "" } else { "s" }
+        ),
+        _ => {
+            writeln!(out, ", tied with:")?;
+            for pick in rest {
+                writeln!(out, "    {}", describe(pick))?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn write_guards_csv(
+    out: &mut impl Write,
+    summaries: &[GuardSummary],
+    bucket: Bucket,
+) -> io::Result<()> {
+    let mut header = vec![
+        "guard".to_owned(),
+        "minutes_asleep".to_owned(),
+        "shifts".to_owned(),
+        "sleepiest".to_owned(),
+        "times".to_owned(),
+    ];
+    header.extend((0..bucket.count()).map(|idx| bucket.start(idx)));
+    writeln!(out, "{}", header.join(","))?;
+
+    for summary in summaries {
+        let mut row = vec![
+            summary.id.to_string(),
+            summary.minutes_asleep.to_string(),
+            summary.shifts.to_string(),
+            bucket.start(summary.sleepiest),
+            summary.times.to_string(),
+        ];
+        row.extend(summary.histogram.iter().map(u32::to_string));
+        writeln!(out, "{}", row.join(","))?;
+    }
+
+    Ok(())
+}
+
+fn write_heatmap(claims: &[Claim], path: &str) -> Result<(), Error> {
+    if Engine::choose(claims) != Engine::Dense {
+        return Err(err_msg("The claims cover too much fabric to draw"));
+    }
+
+    let extension = Path::new(path).extension().and_then(|e| e.to_str());
+    let png = match extension {
+        Some("png") => true,
+        Some("ppm") => false,
+        _ => {
+            return Err(err_msg(format!(
+                "Can't tell the image format of {}, use .png or .ppm",
+                path
+            )))
+        }
+    };
+
+    let image = day3::heatmap(claims);
+    let mut out = Vec::new();
+    if png {
+        image.write_png(&mut out)?;
+    } else {
+        image.write_ppm(&mut out)?;
+    }
+
+    fs::write(path, out).with_context(|_| format!("Failed to write image to {}", path))?;
+    Ok(())
+}
+
+fn verify(path: &Path, record: bool) -> Result<(), Error> {
+    let mut expected = AnswerFile::load(path)?;
+    let mut rows = vec![vec![
+        "Day".to_owned(),
+        "Part 1".to_owned(),
+        "Part 2".to_owned(),
+    ]];
+    let mut problems = Vec::new();
+
+    for day in solution::days() {
+        let answers = match run_day(&day, Parts::Both) {
+            Ok(run) => run.answers,
+            Err(e) => {
+                rows.push(vec![day.number.to_string(), "FAILED".to_owned()]);
+                problems.push(format!("Day {} failed: {}", day.number, e));
+                continue;
+            }
+        };
+
+        let part1 = answers.part1.map(|a| a.recorded()).unwrap_or_default();
+        let part2 = answers.part2.map(|a| a.recorded()).unwrap_or_default();
+
+        if record {
+            expected.record(day.number, &part1, &part2);
+        }
+
+        let recorded = expected.get(day.number);
+        let checks = [
+            Check::new(recorded.map(|r| r.part1.as_str()), &part1),
+            Check::new(recorded.map(|r| r.part2.as_str()), &part2),
+        ];
+
+        let mut row = vec![day.number.to_string()];
+        for (idx, check) in checks.iter().enumerate() {
+            row.push(
+                match check {
+                    Check::Pass => "ok",
+                    Check::Fail { .. } => "FAIL",
+                    Check::Missing => "missing",
+                }
+                .to_owned(),
+            );
+
+            match check {
+                Check::Pass => {}
+                Check::Fail { expected, actual } => problems.push(format!(
+                    "Day {} part {}: expected {}, got {}",
+                    day.number,
+                    idx + 1,
+                    expected,
+                    actual
+                )),
+                Check::Missing => problems.push(format!(
+                    "Day {} part {}: no answer recorded in {}",
+                    day.number,
+                    idx + 1,
+                    path.display()
+                )),
+            }
+        }
+        rows.push(row);
+    }
+
+    print_table(&rows);
+
+    if !problems.is_empty() {
+        eprintln!();
+    }
+    for problem in &problems {
+        eprintln!("{}", problem);
+    }
+
+    if record {
+        expected.save(path)?;
+        println!();
+        println!("Recorded answers in {}", path.display());
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(err_msg(format!(
+            "{} of the answers did not verify",
+            problems.len()
+        )))
+    }
+}
+
+/// Run a day against its embedded input, turning panics into errors.
+fn run_day(day: &Day, parts: Parts) -> Result<Run, Error> {
+    panic::catch_unwind(|| day.run(day.input, parts))
+        .unwrap_or_else(|e| {
+            let msg = e
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| e.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_owned());
+            Err(err_msg(format!("panicked: {}", msg)))
+        })
+        .map_err(|e| name_input(e, input_name(day.number, &None)))
+}
+
+fn run_all(parts: Parts, show_parse: bool, json: bool) -> Result<(), Error> {
+    let mut header = vec!["Day", "Part 1", "Part 2"];
+    if show_parse {
+        header.push("Parse");
+    }
+    header.extend(&["Time 1", "Time 2"]);
+
+    let mut rows = vec![header.into_iter().map(str::to_owned).collect()];
+    let mut pictures = Vec::new();
+    let mut failures = Vec::new();
+
+    for day in solution::days() {
+        let result = run_day(&day, parts);
+        if json {
+            print_json(day.number, &result)?;
+            if let Err(e) = result {
+                failures.push((day.number, e));
+            }
+            continue;
+        }
+
+        match result {
+            Ok(run) => {
+                let mut cell = |part: u32, answer: Option<Answer>| match answer {
+                    Some(Answer::Picture { ref text, .. }) if !text.contains('?') => text.clone(),
+                    Some(answer @ Answer::Picture { .. }) => {
+                        pictures.push((day.number, part, answer));
+                        "(see below)".to_owned()
+                    }
+                    Some(answer) => answer.to_string(),
+                    None => "-".to_owned(),
+                };
+                let time = |time: Option<Duration>| time.map_or("-".to_owned(), format_duration);
+
+                let mut row = vec![
+                    day.number.to_string(),
+                    cell(1, run.answers.part1),
+                    cell(2, run.answers.part2),
+                ];
+                if show_parse {
+                    row.push(format_duration(run.timings.parse));
+                }
+                row.push(time(run.timings.part1));
+                row.push(time(run.timings.part2));
+
+                rows.push(row);
+            }
+            Err(e) => {
+                rows.push(vec![day.number.to_string(), "FAILED".to_owned()]);
+                failures.push((day.number, e));
+            }
+        }
+    }
+
+    if !json {
+        print_table(&rows);
+    }
+
+    for (day, part, picture) in pictures {
+        println!();
+        println!("Day {} part {}:", day, part);
+        println!("{}", picture);
+    }
+
+    for (day, e) in &failures {
+        eprintln!();
+        eprintln!("Day {} failed: {}", day, e);
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(err_msg(format!("{} of the days failed", failures.len())))
+    }
+}
+
+/// Reports can be long, so stop quietly if they're piped into `head`.
+fn ignore_broken_pipe(result: io::Result<()>) -> Result<(), Error> {
+    match result {
+        Err(ref e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+/// Print rows as left-aligned columns, with a rule under the header row.
+fn print_table(rows: &[Vec<String>]) {
+    write_table(&mut io::stdout().lock(), rows).expect("failed printing to stdout");
+}
+
+fn write_table(out: &mut impl Write, rows: &[Vec<String>]) -> io::Result<()> {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|c| {
+            rows.iter()
+                .filter_map(|r| r.get(c))
+                .map(|s| s.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    for (idx, row) in rows.iter().enumerate() {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &w)| format!("{:<w$}", cell, w = w))
+            .collect();
+        writeln!(out, "{}", cells.join("  ").trim_end())?;
+
+        if idx == 0 {
+            let rule: Vec<String> = widths.iter().map(|&w| "-".repeat(w)).collect();
+            writeln!(out, "{}", rule.join("  "))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs_f64();
+
+    if secs >= 1.0 {
+        format!("{:.2} s", secs)
+    } else if secs >= 1e-3 {
+        format!("{:.2} ms", secs * 1e3)
+    } else {
+        format!("{:.2} µs", secs * 1e6)
+    }
+}
+
+fn print_answer(part: u32, answer: &Answer) {
+    if let Answer::Picture { .. } = answer {
+        println!("Part {}:", part);
+        println!("{}", answer);
+    } else {
+        println!("Part {}: {}", part, answer);
+    }
+}
+
+#[derive(Serialize)]
+struct DayReport<'a> {
+    day: u32,
+    part1: Option<&'a Answer>,
+    part2: Option<&'a Answer>,
+    timings: Option<TimingsReport>,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TimingsReport {
+    parse_ms: f64,
+    part1_ms: Option<f64>,
+    part2_ms: Option<f64>,
+}
+
+/// Print the outcome of running a day as a single line of JSON.
+fn print_json(day: u32, result: &Result<Run, Error>) -> Result<(), Error> {
+    let ms = |d: Duration| d.as_secs_f64() * 1e3;
+
+    let report = match result {
+        Ok(run) => DayReport {
+            day,
+            part1: run.answers.part1.as_ref(),
+            part2: run.answers.part2.as_ref(),
+            timings: Some(TimingsReport {
+                parse_ms: ms(run.timings.parse),
+                part1_ms: run.timings.part1.map(ms),
+                part2_ms: run.timings.part2.map(ms),
+            }),
+            error: None,
+        },
+        Err(e) => DayReport {
+            day,
+            part1: None,
+            part2: None,
+            timings: None,
+            error: Some(e.to_string()),
+        },
+    };
+
+    println!("{}", serde_json::to_string(&report)?);
+    Ok(())
+}
+
+fn input_name(day: u32, path: &Option<String>) -> String {
+    match path.as_ref().map(String::as_str) {
+        Some("-") => "<stdin>".to_owned(),
+        Some(path) => path.to_owned(),
+        None => format!("src/data/day{}.txt", day),
+    }
+}
+
+/// Attach the name of the input to any parse errors in `e`.
+fn name_input(e: Error, name: String) -> Error {
+    match e.downcast::<ParseErrors>() {
+        Ok(errors) => errors.with_file(name).into(),
+        Err(e) => e,
+    }
+}
+
+/// Parse the input of a command that only works with one day.
+fn parse_day<S: Solution>(day: u32, default: &str, path: &Option<String>) -> Result<S, Error> {
+    let input = day_input(default, path)?;
+    S::parse(&input).map_err(|e| name_input(e, input_name(day, path)))
+}
+
+/// Read the input at `path`, or fall back to a day's embedded input.
+fn day_input(default: &str, path: &Option<String>) -> Result<String, Error> {
+    match path {
+        Some(path) => read_input(path),
+        None => Ok(default.to_owned()),
+    }
+}
+
+fn read_input(path: &str) -> Result<String, Error> {
+    let mut input = String::new();
+
+    if path == "-" {
+        io::stdin()
+            .read_to_string(&mut input)
+            .context("Failed to read input from stdin")?;
+    } else {
+        input = fs::read_to_string(path)
+            .with_context(|_| format!("Failed to read input from {}", path))?;
+    }
+
+    Ok(input)
 }
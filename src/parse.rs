@@ -0,0 +1,355 @@
+use std::error;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use regex::{Captures, Regex};
+
+/// A problem with a single line of input: where it is and what should have
+/// been there instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldError {
+    /// 1-based column, counted in characters.
+    pub column: usize,
+    pub expected: String,
+}
+
+impl FieldError {
+    pub fn new(column: usize, expected: impl Into<String>) -> FieldError {
+        FieldError {
+            column,
+            expected: expected.into(),
+        }
+    }
+}
+
+impl Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected {} at column {}", self.expected, self.column)
+    }
+}
+
+impl error::Error for FieldError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column, counted in characters.
+    pub column: usize,
+    pub text: String,
+    pub expected: String,
+}
+
+/// The most diagnostics a report lists before it only counts the rest.
+pub const MAX_DIAGNOSTICS: usize = 20;
+
+/// Every line of an input that failed to parse.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseErrors {
+    pub file: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+    /// The number of diagnostics left out after the first `MAX_DIAGNOSTICS`.
+    pub omitted: usize,
+}
+
+impl ParseErrors {
+    pub fn new(mut diagnostics: Vec<Diagnostic>) -> ParseErrors {
+        let omitted = diagnostics.len().saturating_sub(MAX_DIAGNOSTICS);
+        diagnostics.truncate(MAX_DIAGNOSTICS);
+
+        ParseErrors {
+            file: None,
+            diagnostics,
+            omitted,
+        }
+    }
+
+    /// Add a diagnostic, or just count it once the report is full. The text
+    /// is only copied if the diagnostic is kept.
+    pub fn push(&mut self, line: usize, text: &str, error: FieldError) {
+        if self.diagnostics.len() < MAX_DIAGNOSTICS {
+            self.diagnostics.push(Diagnostic {
+                line,
+                column: error.column,
+                text: text.to_owned(),
+                expected: error.expected,
+            });
+        } else {
+            self.omitted += 1;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    pub fn single(line: usize, text: &str, error: FieldError) -> ParseErrors {
+        ParseErrors::new(vec![Diagnostic {
+            line,
+            column: error.column,
+            text: text.to_owned(),
+            expected: error.expected,
+        }])
+    }
+
+    pub fn with_file(self, file: impl Into<String>) -> ParseErrors {
+        ParseErrors {
+            file: Some(file.into()),
+            ..self
+        }
+    }
+}
+
+impl Display for ParseErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let file = self.file.as_ref().map_or("<input>", String::as_str);
+        let count = self.diagnostics.len() + self.omitted;
+
+        write!(
+            f,
+            "{} parse error{} in {}",
+            count,
+            if count == 1 { "" } else { "s" },
+            file
+        )?;
+
+        let gutter = self
+            .diagnostics
+            .iter()
+            .map(|d| d.line.to_string().len())
+            .max()
+            .unwrap_or(0);
+
+        for d in &self.diagnostics {
+            let (text, caret) = snippet(&d.text, d.column);
+
+            writeln!(f)?;
+            writeln!(
+                f,
+                "{}:{}:{}: expected {}",
+                file, d.line, d.column, d.expected
+            )?;
+            writeln!(f, "{:>w$} |", "", w = gutter)?;
+            writeln!(f, "{:>w$} | {}", d.line, text, w = gutter)?;
+            write!(f, "{:>w$} | {:>c$}", "", "^", w = gutter, c = caret)?;
+        }
+
+        if self.omitted > 0 {
+            writeln!(f)?;
+            write!(f, "... and {} more", self.omitted)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl error::Error for ParseErrors {}
+
+const SNIPPET_WIDTH: usize = 72;
+
+/// Cut long lines down to a window around `column`, returning the text to
+/// show and the column of the caret within it.
+fn snippet(text: &str, column: usize) -> (String, usize) {
+    let len = text.chars().count();
+    if len <= SNIPPET_WIDTH {
+        return (text.to_owned(), column);
+    }
+
+    let start = column
+        .saturating_sub(SNIPPET_WIDTH / 2)
+        .min(len - SNIPPET_WIDTH);
+    let end = start + SNIPPET_WIDTH;
+
+    let mut snippet = String::new();
+    let mut caret = column - start;
+
+    if start > 0 {
+        snippet.push_str("...");
+        caret += 3;
+    }
+    snippet.extend(text.chars().skip(start).take(SNIPPET_WIDTH));
+    if end < len {
+        snippet.push_str("...");
+    }
+
+    (snippet, caret)
+}
+
+/// Parse every line of `input` with `parse`, collecting a diagnostic for each
+/// line that fails rather than stopping at the first.
+pub fn parse_lines_with<T, F>(input: &str, parse: F) -> Result<Vec<T>, ParseErrors>
+where
+    F: Fn(&str) -> Result<T, FieldError>,
+{
+    let mut values = Vec::new();
+    let mut errors = ParseErrors::new(Vec::new());
+
+    for (idx, line) in input.lines().enumerate() {
+        match parse(line) {
+            Ok(value) => values.push(value),
+            Err(e) => errors.push(idx + 1, line, e),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(values)
+    } else {
+        Err(errors)
+    }
+}
+
+pub fn parse_lines<T>(input: &str) -> Result<Vec<T>, ParseErrors>
+where
+    T: FromStr<Err = FieldError>,
+{
+    parse_lines_with(input, str::parse)
+}
+
+/// Parse an input that consists of exactly one line.
+pub fn parse_line<T>(input: &str) -> Result<T, ParseErrors>
+where
+    T: FromStr<Err = FieldError>,
+{
+    let mut values = parse_lines(input.trim_end())?;
+
+    match values.len() {
+        1 => Ok(values.remove(0)),
+        0 => Err(ParseErrors::single(
+            1,
+            "",
+            FieldError::new(1, "a line of input"),
+        )),
+        _ => {
+            let text = input.lines().nth(1).unwrap_or("");
+            Err(ParseErrors::single(
+                2,
+                text,
+                FieldError::new(1, "end of input"),
+            ))
+        }
+    }
+}
+
+/// A line format built from a sequence of regex fragments, each paired with a
+/// description of what it matches. When a line doesn't match, the fragments
+/// are tried one at a time to find the column where it goes wrong.
+///
+/// The regexes are compiled the first time they are needed, so patterns
+/// should live in a `static` to compile them once rather than once per line.
+pub struct Pattern {
+    parts: &'static [(&'static str, &'static str)],
+    full: OnceLock<Regex>,
+    prefixes: OnceLock<Vec<Regex>>,
+}
+
+impl Pattern {
+    pub const fn new(parts: &'static [(&'static str, &'static str)]) -> Pattern {
+        Pattern {
+            parts,
+            full: OnceLock::new(),
+            prefixes: OnceLock::new(),
+        }
+    }
+
+    pub fn captures<'p, 't>(&'p self, text: &'t str) -> Result<Fields<'p, 't>, FieldError> {
+        let re = self.full.get_or_init(|| self.regex(self.parts.len(), true));
+
+        match re.captures(text) {
+            Some(caps) => Ok(Fields {
+                pattern: self,
+                text,
+                caps,
+            }),
+            None => Err(self.diagnose(text)),
+        }
+    }
+
+    fn regex(&self, parts: usize, complete: bool) -> Regex {
+        let mut re = String::from("^");
+        for (fragment, _) in &self.parts[..parts] {
+            re.push_str(fragment);
+        }
+        if complete {
+            re.push_str(r"\s*$");
+        }
+
+        Regex::new(&re).unwrap()
+    }
+
+    /// A regex for each leading run of fragments: the first fragment, the
+    /// first two, and so on.
+    fn prefixes(&self) -> &[Regex] {
+        self.prefixes.get_or_init(|| {
+            (1..=self.parts.len())
+                .map(|parts| self.regex(parts, false))
+                .collect()
+        })
+    }
+
+    /// The description of the fragment containing capture group `idx`.
+    fn describe_group(&self, idx: usize) -> &'static str {
+        for (prefix, (_, description)) in self.prefixes().iter().zip(self.parts) {
+            if prefix.captures_len() > idx {
+                return description;
+            }
+        }
+
+        "a field"
+    }
+
+    fn diagnose(&self, text: &str) -> FieldError {
+        let mut matched = 0;
+
+        for (prefix, (_, description)) in self.prefixes().iter().zip(self.parts) {
+            match prefix.find(text) {
+                Some(m) => matched = m.end(),
+                None => return FieldError::new(column(text, matched), *description),
+            }
+        }
+
+        FieldError::new(column(text, matched), "end of line")
+    }
+}
+
+/// The captured fields of a line that matched a `Pattern`.
+pub struct Fields<'p, 't> {
+    pattern: &'p Pattern,
+    text: &'t str,
+    caps: Captures<'t>,
+}
+
+impl<'p, 't> Fields<'p, 't> {
+    pub fn has(&self, idx: usize) -> bool {
+        self.caps.get(idx).is_some()
+    }
+
+    pub fn str(&self, idx: usize) -> Result<&'t str, FieldError> {
+        self.caps
+            .get(idx)
+            .map(|m| m.as_str())
+            .ok_or_else(|| self.error(idx, self.pattern.describe_group(idx)))
+    }
+
+    pub fn get<T>(&self, idx: usize) -> Result<T, FieldError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.str(idx)?.parse().map_err(|e| {
+            let expected = self.pattern.describe_group(idx);
+            self.error(idx, format!("{} ({})", expected, e))
+        })
+    }
+
+    /// An error pointing at the start of field `idx`.
+    pub fn error(&self, idx: usize, expected: impl Into<String>) -> FieldError {
+        let start = self.caps.get(idx).map_or(0, |m| m.start());
+        FieldError::new(column(self.text, start), expected)
+    }
+}
+
+/// The 1-based character column of byte offset `offset` in `text`.
+pub fn column(text: &str, offset: usize) -> usize {
+    text[..offset].chars().count() + 1
+}
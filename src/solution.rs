@@ -0,0 +1,239 @@
+use std::error;
+use std::fmt::{self, Display};
+use std::time::{Duration, Instant};
+
+use failure::Error;
+use serde_derive::Serialize;
+
+use crate::bench::{self, Bench};
+use crate::{day1, day10, day2, day3, day4, day5, day6, day7, day8, day9};
+
+/// A day's puzzle. `parse` should only do the work that both parts need, so
+/// that running a single part skips anything specific to the other.
+pub trait Solution: Sized {
+    type Part1: Into<Answer>;
+    type Part2: Into<Answer>;
+
+    fn parse(input: &str) -> Result<Self, Error>;
+    fn part1(&self) -> Result<Self::Part1, Error>;
+    fn part2(&self) -> Result<Self::Part2, Error>;
+}
+
+/// The answer to one part of a puzzle.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Answer {
+    Number(i64),
+    Text(String),
+    /// A picture drawn by the puzzle, along with the text it spells out.
+    Picture {
+        rows: Vec<String>,
+        text: String,
+    },
+}
+
+impl Answer {
+    /// The answer as it is stored in the answers file. Pictures are stored as
+    /// the text they spell, or as their rows without trailing spaces if the
+    /// text isn't recognised.
+    pub fn recorded(&self) -> String {
+        match self {
+            Answer::Picture { text, .. } if !text.contains('?') => text.clone(),
+            Answer::Picture { rows, .. } => rows
+                .iter()
+                .map(|row| row.trim_end())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            answer => answer.to_string(),
+        }
+    }
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+            Answer::Picture { rows, .. } => write!(f, "{}", rows.join("\n")),
+        }
+    }
+}
+
+impl From<i32> for Answer {
+    fn from(n: i32) -> Answer {
+        Answer::Number(n.into())
+    }
+}
+
+impl From<u32> for Answer {
+    fn from(n: u32) -> Answer {
+        Answer::Number(n.into())
+    }
+}
+
+impl From<u64> for Answer {
+    fn from(n: u64) -> Answer {
+        Answer::Number(n as i64)
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(n: usize) -> Answer {
+        Answer::Number(n as i64)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(s: String) -> Answer {
+        Answer::Text(s)
+    }
+}
+
+/// Which parts of a puzzle to run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Parts {
+    Both,
+    Part1,
+    Part2,
+}
+
+impl Parts {
+    pub fn part1(self) -> bool {
+        self != Parts::Part2
+    }
+
+    pub fn part2(self) -> bool {
+        self != Parts::Part1
+    }
+}
+
+/// The answers to the parts that were run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Answers {
+    pub part1: Option<Answer>,
+    pub part2: Option<Answer>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timings {
+    pub parse: Duration,
+    pub part1: Option<Duration>,
+    pub part2: Option<Duration>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub answers: Answers,
+    pub timings: Timings,
+}
+
+pub struct Day {
+    pub number: u32,
+    pub input: &'static str,
+    run: fn(&str, Parts) -> Result<Run, Error>,
+    bench: fn(&str, Parts, usize) -> Result<Bench, Error>,
+}
+
+impl Day {
+    pub fn new<S: Solution>(number: u32, input: &'static str) -> Day {
+        Day {
+            number,
+            input,
+            run: run::<S>,
+            bench: bench::bench::<S>,
+        }
+    }
+
+    pub fn run(&self, input: &str, parts: Parts) -> Result<Run, Error> {
+        (self.run)(input, parts)
+    }
+
+    pub fn solve(&self, input: &str) -> Result<Answers, Error> {
+        Ok(self.run(input, Parts::Both)?.answers)
+    }
+
+    pub fn bench(&self, input: &str, parts: Parts, iterations: usize) -> Result<Bench, Error> {
+        (self.bench)(input, parts, iterations)
+    }
+}
+
+fn run<S: Solution>(input: &str, parts: Parts) -> Result<Run, Error> {
+    let (solution, parse) = timed(|| S::parse(input));
+    let solution = solution?;
+
+    let (part1, part1_time) = if parts.part1() {
+        let (answer, time) = timed(|| solution.part1());
+        (Some(answer?.into()), Some(time))
+    } else {
+        (None, None)
+    };
+
+    let (part2, part2_time) = if parts.part2() {
+        let (answer, time) = timed(|| solution.part2());
+        (Some(answer?.into()), Some(time))
+    } else {
+        (None, None)
+    };
+
+    Ok(Run {
+        answers: Answers { part1, part2 },
+        timings: Timings {
+            parse,
+            part1: part1_time,
+            part2: part2_time,
+        },
+    })
+}
+
+pub(crate) fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+pub fn days() -> Vec<Day> {
+    vec![
+        Day::new::<day1::Day1>(1, day1::INPUT),
+        Day::new::<day2::Day2>(2, day2::INPUT),
+        Day::new::<day3::Day3>(3, day3::INPUT),
+        Day::new::<day4::Day4>(4, day4::INPUT),
+        Day::new::<day5::Day5>(5, day5::INPUT),
+        Day::new::<day6::Day6>(6, day6::INPUT),
+        Day::new::<day7::Day7>(7, day7::INPUT),
+        Day::new::<day8::Day8>(8, day8::INPUT),
+        Day::new::<day9::Day9>(9, day9::INPUT),
+        Day::new::<day10::Day10>(10, day10::INPUT),
+    ]
+}
+
+pub fn find_day(number: u32) -> Result<Day, UnknownDay> {
+    let mut days = days();
+
+    match days.iter().position(|d| d.number == number) {
+        Some(idx) => Ok(days.swap_remove(idx)),
+        None => Err(UnknownDay {
+            day: number,
+            available: days.iter().map(|d| d.number).collect(),
+        }),
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownDay {
+    pub day: u32,
+    pub available: Vec<u32>,
+}
+
+impl Display for UnknownDay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let available: Vec<String> = self.available.iter().map(u32::to_string).collect();
+        write!(
+            f,
+            "Unknown day {} (available days: {})",
+            self.day,
+            available.join(", ")
+        )
+    }
+}
+
+impl error::Error for UnknownDay {}